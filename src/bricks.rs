@@ -1,35 +1,40 @@
 use colored::Color;
-use rand::seq::IteratorRandom;
 use strum::IntoEnumIterator;
 
-type Pixel = (isize, isize);
+use crate::{
+    randomizer::RandomizerKind,
+    rotation::{KickTable, Rotation, Turn},
+};
 
+pub type Pixel = (isize, isize);
+
+/// cells of the spawn orientation (x right, y up, relative to the brick origin) and color
 pub type BrickInfo<'a> = (&'a [Pixel], Color);
 
-// classic
-pub static SHAPE_I: BrickInfo = (&[(0, 2), (0, 1), (0, -1)], Color::Cyan);
-pub static SHAPE_O: BrickInfo = (&[(0, 1), (1, 1), (1, 0)], Color::Yellow);
+// classic, spawn orientations follow the guideline (flat side down, T pointing up)
+pub static SHAPE_I: BrickInfo = (&[(-1, 0), (0, 0), (1, 0), (2, 0)], Color::Cyan);
+pub static SHAPE_O: BrickInfo = (&[(0, 0), (1, 0), (0, 1), (1, 1)], Color::Yellow);
 pub static SHAPE_T: BrickInfo = (
-    &[(-1, 0), (0, 1), (1, 0)],
+    &[(-1, 0), (0, 0), (1, 0), (0, 1)],
     Color::TrueColor {
         r: 0x64,
         g: 0x95,
         b: 0xed,
     },
 );
-pub static SHAPE_S: BrickInfo = (&[(1, 0), (0, 1), (1, -1)], Color::Red);
+pub static SHAPE_S: BrickInfo = (&[(-1, 0), (0, 0), (0, 1), (1, 1)], Color::Red);
 // featured
 pub static SHAPE_Z: BrickInfo = (
-    &[(0, 1), (-1, 0), (-1, -1)],
+    &[(-1, 1), (0, 1), (0, 0), (1, 0)],
     Color::TrueColor {
         r: 0xec,
         g: 0xc5,
         b: 0x44,
     },
 );
-pub static SHAPE_J: BrickInfo = (&[(0, 1), (0, -1), (-1, -1)], Color::Green);
+pub static SHAPE_J: BrickInfo = (&[(-1, 1), (-1, 0), (0, 0), (1, 0)], Color::Green);
 pub static SHAPE_L: BrickInfo = (
-    &[(0, 1), (0, -1), (1, -1)],
+    &[(-1, 0), (0, 0), (1, 0), (1, 1)],
     Color::TrueColor {
         r: 0xef,
         g: 0x6b,
@@ -47,7 +52,7 @@ pub static SHAPE_L: BrickInfo = (
 // );
 // feature
 pub static SHAPE_DOT: BrickInfo = (
-    &[],
+    &[(0, 0)],
     Color::TrueColor {
         r: 0x80,
         g: 0x00,
@@ -56,7 +61,7 @@ pub static SHAPE_DOT: BrickInfo = (
 );
 
 pub static SHAPE_ANGLE: BrickInfo = (
-    &[(0, 1), (1, 0)],
+    &[(0, 0), (0, 1), (1, 0)],
     Color::TrueColor {
         r: 0x00,
         g: 0x60,
@@ -69,7 +74,7 @@ pub static SHAPE_ANGLE: BrickInfo = (
 WW
 */
 pub static SHAPE_W: BrickInfo = (
-    &[(0, -1), (1, 0), (-1, -1), (1, 1)],
+    &[(0, 0), (0, -1), (1, 0), (-1, -1), (1, 1)],
     Color::TrueColor {
         r: 0x2b,
         g: 0xdd,
@@ -77,7 +82,7 @@ pub static SHAPE_W: BrickInfo = (
     },
 );
 pub static SHAPE_BEAN: BrickInfo = (
-    &[(0, 1)],
+    &[(0, 0), (0, 1)],
     Color::TrueColor {
         r: 0xe8,
         g: 0x7d,
@@ -86,7 +91,7 @@ pub static SHAPE_BEAN: BrickInfo = (
 );
 
 pub static SHAPE_DESK: BrickInfo = (
-    &[(-1, 1), (1, 1), (1, 0), (-1, 0)],
+    &[(-1, 1), (1, 1), (1, 0), (0, 0), (-1, 0)],
     Color::TrueColor {
         r: 0x20,
         g: 0x60,
@@ -114,27 +119,49 @@ impl BrickType {
     pub fn is_featured(&self) -> bool {
         *self as u8 >= 7
     }
+
+    /// rotation centre, doubled so that the half-cell centres of `I` and `O`
+    /// stay integral. `(0, 0)` means the origin cell itself.
+    pub fn pivot(&self) -> Pixel {
+        match self {
+            BrickType::I => (1, -1),
+            BrickType::O => (1, 1),
+            _ => (0, 0),
+        }
+    }
+
+    pub fn kick_table(&self) -> KickTable {
+        match self {
+            BrickType::I => KickTable::I,
+            BrickType::O | BrickType::Dot => KickTable::Fixed,
+            BrickType::Angle | BrickType::Bean => KickTable::Small,
+            BrickType::T
+            | BrickType::S
+            | BrickType::Z
+            | BrickType::L
+            | BrickType::J
+            | BrickType::Desk
+            | BrickType::W => KickTable::Jlstz,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Brick {
     pub brick_type: BrickType,
+    /// occupied cells in the current orientation, relative to the brick origin
     pub pixels: Vec<Pixel>,
     pub color: Color,
+    pub rotation: Rotation,
 }
 
 impl Brick {
     pub fn limits(&self) -> (isize, isize, isize, isize) {
-        if self.pixels.len() == 0 {
+        if self.pixels.is_empty() {
             return (0, 0, 0, 0);
         }
         self.pixels.iter().fold(
-            (
-                std::isize::MAX,
-                std::isize::MIN,
-                std::isize::MAX,
-                std::isize::MIN,
-            ),
+            (isize::MAX, isize::MIN, isize::MAX, isize::MIN),
             |(min_x, max_x, min_y, max_y), &(x, y)| {
                 (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
             },
@@ -165,33 +192,113 @@ impl Brick {
             brick_type: b,
             pixels: ps.0.to_vec(),
             color: ps.1,
+            rotation: Rotation::Spawn,
         }
     }
+
+    /// turn clockwise around the pivot, without any collision check.
     pub fn rotate(&mut self) {
-        for i in 0..self.pixels.len() {
-            let (x, y) = self.pixels[i];
-            self.pixels[i] = (y, -x);
+        let (px, py) = self.brick_type.pivot();
+        // (x, y) -> (c.x + (y - c.y), c.y - (x - c.x)) with c = pivot / 2
+        for p in self.pixels.iter_mut() {
+            let (x, y) = *p;
+            *p = (y + (px - py) / 2, (px + py) / 2 - x);
         }
+        self.rotation = self.rotation.cw();
     }
-    pub fn pixels_info(&self, offset_x: isize, offset_y: isize) -> Vec<(isize, isize)> {
-        let mut absolute_positions: Vec<(isize, isize)> = vec![(offset_x, offset_y)];
-        for e in &self.pixels {
-            absolute_positions.push((offset_x + e.0, (offset_y - e.1)))
+
+    pub fn rotate_ccw(&mut self) {
+        self.turn(Turn::Ccw)
+    }
+
+    pub fn rotate_180(&mut self) {
+        self.turn(Turn::Half)
+    }
+
+    pub fn turn(&mut self, turn: Turn) {
+        let times = match turn {
+            Turn::Cw => 1,
+            Turn::Half => 2,
+            Turn::Ccw => 3,
+        };
+        for _ in 0..times {
+            self.rotate();
         }
-        absolute_positions
     }
 
+    /// board positions (x right, y down) of the cells when the origin is at `offset`
+    pub fn pixels_info(&self, offset_x: isize, offset_y: isize) -> Vec<(isize, isize)> {
+        self.pixels
+            .iter()
+            .map(|e| (offset_x + e.0, offset_y - e.1))
+            .collect()
+    }
+
+    /// a uniformly random brick, with or without the feature bricks
+    #[deprecated(note = "deal from a seeded `Randomizer`, see `RandomizerKind::build`")]
     pub fn random(feature_mode: bool) -> Brick {
-        let mut rng = rand::thread_rng();
-        let it = BrickType::iter();
-        Brick::new(
-            match feature_mode {
-                true => it.choose(&mut rng),
-                false => it
-                    .filter_map(|bt| if !bt.is_featured() { Some(bt) } else { None })
-                    .choose(&mut rng),
-            }
-            .unwrap(),
-        )
+        let mut randomizer = RandomizerKind::Random.build(feature_mode, Some(rand::random()));
+        Brick::new(randomizer.next())
+    }
+}
+
+/// the smallest board the bricks fit on, as `(width, height)`: every brick must spawn
+/// inside the walls at the spawn column and have room for all its orientations
+pub fn min_board_size(feature_brick: bool) -> (usize, usize) {
+    let (mut left, mut right, mut below) = (0, 0, 0);
+    let (mut w, mut h) = (0, 0);
+    for bt in BrickType::iter().filter(|bt| feature_brick || !bt.is_featured()) {
+        let mut b = Brick::new(bt);
+        for &(x, y) in &b.pixels_info(0, 0) {
+            left = left.max(-x);
+            right = right.max(x);
+            below = below.max(y);
+        }
+        for _ in 0..4 {
+            let (bw, bh) = b.get_size();
+            w = w.max(bw);
+            h = h.max(bh);
+            b.rotate();
+        }
     }
+    // 出生列向左取整, 左右两边的空间不一样大
+    let fits = |w: usize| {
+        let center = (w.saturating_sub(1) / 2) as isize;
+        center >= left && w as isize - 1 - center >= right
+    };
+    let width = (w..).find(|&w| fits(w)).unwrap();
+    (width, h.max(below as usize + 1))
+}
+
+#[test]
+fn rotation_test() {
+    // O rotates in place
+    let mut o = Brick::new(BrickType::O);
+    o.rotate();
+    let mut cells = o.pixels.clone();
+    cells.sort();
+    assert_eq!(cells, vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+
+    // I: 0 -> R is the third column of its 4x4 box
+    let mut i = Brick::new(BrickType::I);
+    i.rotate();
+    assert_eq!(i.pixels, vec![(1, 1), (1, 0), (1, -1), (1, -2)]);
+    assert_eq!(i.rotation, Rotation::Right);
+
+    // four turns are the identity for every brick
+    for bt in BrickType::iter() {
+        let mut b = Brick::new(bt);
+        for _ in 0..4 {
+            b.rotate();
+        }
+        assert_eq!(b.pixels, Brick::new(bt).pixels);
+        assert_eq!(b.rotation, Rotation::Spawn);
+    }
+}
+
+#[test]
+fn min_board_size_test() {
+    // the I brick is 4 long, 2 cells right of the spawn column
+    assert_eq!(min_board_size(false), (4, 4));
+    assert_eq!(min_board_size(true), (4, 4));
 }
@@ -0,0 +1,248 @@
+//! Command line: flags override the config file and environment variables.
+
+use std::{fs, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use tetris_rs::{
+    config,
+    env::{EnvConfig, Source},
+    mode::Mode,
+    scores::{self, Category, Score, TABLE_SIZE},
+    texture::{Texture, TexturePreset},
+    theme::{ColorDepth, Theme, ThemePreset},
+};
+
+use crate::menu::{clock, score_line};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "tetris",
+    version,
+    about = "terminal based tetris game written in rust."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub play: PlayArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct PlayArgs {
+    /// start a game of this mode right away instead of opening the menu [marathon, sprint, ultra]
+    #[arg(short, long)]
+    pub mode: Option<Mode>,
+
+    /// randomizer seed, the same seed deals the same bricks
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// board width in cells
+    #[arg(long)]
+    pub width: Option<usize>,
+
+    /// board height in cells
+    #[arg(long)]
+    pub height: Option<usize>,
+
+    /// level to start at
+    #[arg(short, long)]
+    pub level: Option<usize>,
+
+    /// [ascii, brackets, blocks, compact]
+    #[arg(long)]
+    pub texture: Option<TexturePreset>,
+
+    /// [classic, guideline, colorblind, contrast, mono]
+    #[arg(long)]
+    pub theme: Option<ThemePreset>,
+
+    /// turn colors off, like NO_COLOR
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// read this config file instead of $XDG_CONFIG_HOME/tetris-rs/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// watch a recorded game
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// save a replay of the last game played to this file
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// no terminal: play the replay (or a game without input) at full speed and print the result
+    #[arg(long)]
+    pub headless: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// show the settings in effect or write a config file with the defaults
+    Config {
+        /// print the settings in effect as a config file
+        #[arg(long, conflicts_with = "init")]
+        print: bool,
+        /// write the defaults to the config file
+        #[arg(long)]
+        init: bool,
+    },
+    /// the high score tables: best saved games per mode, board size, bricks and scoring
+    Scores {
+        /// only the tables of this mode
+        #[arg(short, long)]
+        mode: Option<Mode>,
+    },
+    /// totals over every saved game
+    Stats,
+}
+
+impl PlayArgs {
+    /// the flags that were given, over `cfg`
+    pub fn apply(&self, cfg: &mut EnvConfig) {
+        if let Some(mode) = self.mode {
+            cfg.mode = mode;
+        }
+        if self.seed.is_some() {
+            cfg.seed = self.seed;
+        }
+        if let Some(width) = self.width {
+            cfg.width = width;
+            cfg.set_source("gameplay.width", Source::Flag("--width".to_string()));
+        }
+        if let Some(height) = self.height {
+            cfg.height = height;
+            cfg.set_source("gameplay.height", Source::Flag("--height".to_string()));
+        }
+        if let Some(level) = self.level {
+            cfg.start_level = level;
+            cfg.set_source("gameplay.start_level", Source::Flag("--level".to_string()));
+        }
+        if let Some(texture) = self.texture {
+            cfg.texture = Texture::preset(texture);
+        }
+        if let Some(theme) = self.theme {
+            cfg.theme = Theme::preset(theme);
+        }
+        if self.no_color {
+            cfg.color = Some(ColorDepth::None);
+        }
+    }
+
+    /// the config file in use
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(config::path)
+    }
+}
+
+/// `tetris config --print` / `--init`
+pub fn config_command(
+    print: bool,
+    init: bool,
+    args: &PlayArgs,
+    cfg: &EnvConfig,
+) -> Result<(), String> {
+    if print {
+        print!("{}", config::to_toml(cfg));
+        return Ok(());
+    }
+    if !init {
+        return Err("pass --print or --init".to_string());
+    }
+    let path = args
+        .config_path()
+        .ok_or("no config directory, set XDG_CONFIG_HOME")?;
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, config::to_toml(&EnvConfig::default())).map_err(|e| e.to_string())?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+fn saved_scores() -> Result<Vec<Score>, String> {
+    let path = scores::path().ok_or("no data directory, set XDG_DATA_HOME")?;
+    scores::load(&path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// `tetris scores`
+pub fn scores_command(mode: Option<Mode>) -> Result<(), String> {
+    let saved = saved_scores()?;
+    let categories: Vec<Category> = scores::categories(&saved)
+        .into_iter()
+        .filter(|c| mode.is_none_or(|m| c.mode == m))
+        .collect();
+    if categories.is_empty() {
+        println!("no games saved yet");
+        return Ok(());
+    }
+    for category in categories {
+        println!("{}:", category);
+        for (i, s) in scores::table(&saved, category, TABLE_SIZE)
+            .iter()
+            .enumerate()
+        {
+            println!("{}", score_line(i + 1, s));
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// `tetris stats`
+pub fn stats_command() -> Result<(), String> {
+    let saved = saved_scores()?;
+    let frames: u64 = saved.iter().map(|s| s.frames).sum();
+    println!("games played: {}", saved.len());
+    println!("time played: {}", clock(frames));
+    println!(
+        "rows cleared: {}",
+        saved.iter().map(|s| s.rows).sum::<usize>()
+    );
+    for mode in Mode::ALL {
+        let games: Vec<&Score> = saved.iter().filter(|s| s.mode == mode).collect();
+        if games.is_empty() {
+            continue;
+        }
+        let best = scores::best(&saved, mode, 1)[0];
+        let best = match mode {
+            Mode::Sprint if best.complete => format!("best time {}", clock(best.frames)),
+            Mode::Sprint => "no finished sprint".to_string(),
+            _ => format!("best score {}", best.score),
+        };
+        println!("{}: {} games, {}", mode, games.len(), best);
+    }
+    Ok(())
+}
+
+#[test]
+fn cli_test() {
+    let cli =
+        Cli::try_parse_from(["tetris", "-m", "sprint", "--width", "12", "--no-color"]).unwrap();
+    let mut cfg = EnvConfig::default();
+    cli.play.apply(&mut cfg);
+    assert_eq!(cfg.mode, Mode::Sprint);
+    assert_eq!(cfg.width, 12);
+    assert_eq!(cfg.color, Some(ColorDepth::None));
+    assert!(cli.command.is_none());
+
+    let cli = Cli::try_parse_from(["tetris", "config", "--print"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Command::Config { print: true, .. })
+    ));
+    assert!(Cli::try_parse_from(["tetris", "--mode", "zen"]).is_err());
+    let cli = Cli::try_parse_from(["tetris", "scores", "-m", "ultra"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Command::Scores {
+            mode: Some(Mode::Ultra)
+        })
+    ));
+}
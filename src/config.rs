@@ -0,0 +1,402 @@
+//! The config file: `$XDG_CONFIG_HOME/tetris-rs/config.toml`
+//! (`~/.config/tetris-rs/config.toml` when `XDG_CONFIG_HOME` is not set).
+//!
+//! Settings are layered: defaults < config file < environment variables < command line.
+//! `parse` applies a file on top of a config, `to_toml` writes one back out.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use colored::Color;
+use strum::IntoEnumIterator;
+use toml_edit::{value, DocumentMut, ImDocument, Item, Table, TableLike};
+
+use crate::{
+    bricks::BrickType,
+    env::{ConfigError, EnvConfig, Source},
+    keys::{parse_keys, Control, Key, KeyBindings, KeysPreset},
+    texture::{Texture, TexturePreset},
+    theme::{color_name, parse_color, Theme, ThemePreset},
+};
+
+/// sections in the order they are applied: presets before the single values overriding them
+const SECTIONS: [&str; 6] = [
+    "gameplay", "handling", "display", "keys", "texture", "colors",
+];
+
+/// where the config file is looked for
+pub fn path() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    }
+    .map(|dir| dir.join("tetris-rs").join("config.toml"))
+}
+
+/// `base` with the file at `path` applied, an error when it can't be read
+pub fn load(path: &Path, base: EnvConfig) -> Result<EnvConfig, ConfigError> {
+    let in_file = |error| ConfigError::File {
+        path: path.to_path_buf(),
+        error: Box::new(error),
+    };
+    let src = fs::read_to_string(path).map_err(|e| in_file(ConfigError::Read(e.to_string())))?;
+    let mut cfg = parse(&src, base).map_err(in_file)?;
+    // 这个文件里的设置记下文件名
+    for source in cfg.sources.values_mut() {
+        if let Source::File { path: p @ None, .. } = source {
+            *p = Some(path.to_path_buf());
+        }
+    }
+    Ok(cfg)
+}
+
+/// `base` with the config file at `path()` applied, `base` itself when there is none
+pub fn load_default(base: EnvConfig) -> Result<EnvConfig, ConfigError> {
+    match path() {
+        // 只有默认位置的文件可以不存在, 读不了(比如没有权限)还是要报错
+        Some(path) if path.try_exists().unwrap_or(true) => load(&path, base),
+        _ => Ok(base),
+    }
+}
+
+/// `base` with the settings of a config file applied.
+/// errors know their line, e.g. `3: gameplay.width should be a number`.
+pub fn parse(src: &str, base: EnvConfig) -> Result<EnvConfig, ConfigError> {
+    let doc = ImDocument::parse(src).map_err(|e| ConfigError::Syntax {
+        line: e.span().map_or(1, |span| line_of(src, span.start)),
+        message: e.message().to_string(),
+    })?;
+    let root = doc.as_table();
+    for (name, _) in root.iter() {
+        if !SECTIONS.contains(&name) {
+            return Err(ConfigError::UnknownSection {
+                line: key_line(src, root, name),
+                section: name.to_string(),
+            });
+        }
+    }
+
+    let mut cfg = base;
+    for section in SECTIONS {
+        let table = match root.get(section) {
+            Some(item) => item.as_table_like().ok_or_else(|| ConfigError::Setting {
+                line: Some(key_line(src, root, section)),
+                setting: section.to_string(),
+                reason: "should be a table".to_string(),
+            })?,
+            None => continue,
+        };
+        for (key, item) in table.iter() {
+            let line = key_line(src, table, key);
+            let setting = format!("{}.{}", section, key);
+            set(&mut cfg, section, key, item).map_err(|reason| ConfigError::Setting {
+                line: Some(line),
+                setting: setting.clone(),
+                reason,
+            })?;
+            cfg.set_source(&setting, Source::File { path: None, line });
+        }
+    }
+    Ok(cfg)
+}
+
+fn line_of(src: &str, offset: usize) -> usize {
+    src[..offset.min(src.len())].matches('\n').count() + 1
+}
+
+fn key_line(src: &str, table: &dyn TableLike, key: &str) -> usize {
+    table
+        .key(key)
+        .and_then(|k| k.span())
+        .map_or(1, |span| line_of(src, span.start))
+}
+
+fn string(item: &Item) -> Result<&str, String> {
+    item.as_str()
+        .ok_or_else(|| "should be a string".to_string())
+}
+
+fn boolean(item: &Item) -> Result<bool, String> {
+    item.as_bool()
+        .ok_or_else(|| "should be true or false".to_string())
+}
+
+fn number(item: &Item) -> Result<usize, String> {
+    item.as_integer()
+        .and_then(|v| usize::try_from(v).ok())
+        .ok_or_else(|| "should be a number".to_string())
+}
+
+fn millis(item: &Item) -> Result<Duration, String> {
+    number(item)
+        .map(|v| Duration::from_millis(v as u64))
+        .map_err(|_| "should be a number of milliseconds".to_string())
+}
+
+/// a string parsed into one of `names`
+fn choice<T: std::str::FromStr>(item: &Item, names: &str) -> Result<T, String> {
+    string(item)?
+        .parse()
+        .map_err(|_| format!("should be one of {}", names))
+}
+
+/// a color, `none` for the terminal's default color
+fn color(item: &Item) -> Result<Option<Color>, String> {
+    match string(item)? {
+        "none" => Ok(None),
+        s => parse_color(s)
+            .map(Some)
+            .ok_or_else(|| "should be a color name, #rrggbb or none".to_string()),
+    }
+}
+
+/// keys like `"h, left"` or `["h", "left"]`
+fn keys(item: &Item) -> Result<Vec<Key>, String> {
+    match item.as_array() {
+        Some(array) => array
+            .iter()
+            .map(|v| v.as_str().ok_or("should be a list of key names")?.parse())
+            .collect(),
+        None => parse_keys(string(item)?),
+    }
+}
+
+fn set(cfg: &mut EnvConfig, section: &str, key: &str, item: &Item) -> Result<(), String> {
+    match (section, key) {
+        ("gameplay", "mode") => cfg.mode = choice(item, "marathon, sprint, ultra")?,
+        ("gameplay", "width") => cfg.width = number(item)?,
+        ("gameplay", "height") => cfg.height = number(item)?,
+        ("gameplay", "feature_brick") => cfg.feature_brick = boolean(item)?,
+        ("gameplay", "accelerate") => cfg.accelerate = boolean(item)?,
+        ("gameplay", "start_level") => cfg.start_level = number(item)?,
+        ("gameplay", "level_lines") => cfg.level_lines = number(item)?,
+        ("gameplay", "randomizer") => {
+            cfg.randomizer = choice(item, "bag7, bag14, nes, tgm, random")?
+        }
+        ("gameplay", "scoring") => cfg.scoring = choice(item, "classic, guideline")?,
+        ("gameplay", "hold") => cfg.hold = boolean(item)?,
+        ("gameplay", "lock_delay") => cfg.lock_delay = millis(item)?,
+        ("gameplay", "lock_reset") => cfg.lock_reset = choice(item, "move, step, none")?,
+        ("gameplay", "lock_reset_limit") => cfg.lock_reset_limit = number(item)?,
+        ("handling", "das") => cfg.das = millis(item)?,
+        ("handling", "arr") => cfg.arr = millis(item)?,
+        ("handling", "sdf") => {
+            cfg.sdf = item
+                .as_float()
+                .or_else(|| item.as_integer().map(|v| v as f64))
+                .ok_or("should be a number (or inf)")?
+        }
+        ("handling", "keys") => {
+            cfg.keys = KeyBindings::preset(choice::<KeysPreset>(item, "default, vim, wasd")?)
+        }
+        ("keys", _) => {
+            let control = key
+                .parse::<Control>()
+                .map_err(|_| "is not a control".to_string())?;
+            cfg.keys.set(control, keys(item)?);
+        }
+        ("display", "texture") => {
+            cfg.texture = Texture::preset(choice::<TexturePreset>(
+                item,
+                "ascii, brackets, blocks, compact",
+            )?)
+        }
+        ("display", "theme") => {
+            cfg.theme = Theme::preset(choice::<ThemePreset>(
+                item,
+                "classic, guideline, colorblind, contrast, mono",
+            )?)
+        }
+        ("display", "color") => {
+            cfg.color = match string(item)? {
+                "auto" => None,
+                _ => Some(choice(item, "auto, truecolor, 256, 16, none")?),
+            }
+        }
+        ("texture", "full") => cfg.texture.full = string(item)?.to_string(),
+        ("texture", "wall") => cfg.texture.wall = string(item)?.to_string(),
+        ("texture", "empty") => cfg.texture.empty = string(item)?.to_string(),
+        ("texture", "shadow") => cfg.texture.shadow = string(item)?.to_string(),
+        ("colors", "wall") => cfg.theme.wall = color(item)?,
+        ("colors", "ghost") => cfg.theme.ghost = color(item)?,
+        ("colors", "text") => cfg.theme.text = color(item)?,
+        ("colors", "accent") => cfg.theme.accent = color(item)?,
+        ("colors", _) => {
+            let i = BrickType::iter()
+                .position(|t| format!("{:?}", t).to_lowercase() == key)
+                .ok_or("is not a brick or one of wall, ghost, text, accent")?;
+            cfg.theme.pieces[i] = color(item)?.ok_or("should be a color name or #rrggbb")?;
+        }
+        _ => return Err("is not a known setting".to_string()),
+    }
+    Ok(())
+}
+
+/// `cfg` as a config file, read back by `parse` to the same config
+pub fn to_toml(cfg: &EnvConfig) -> String {
+    let mut doc = DocumentMut::new();
+
+    let mut gameplay = Table::new();
+    gameplay["mode"] = value(cfg.mode.to_string());
+    gameplay["width"] = value(cfg.width as i64);
+    gameplay["height"] = value(cfg.height as i64);
+    gameplay["feature_brick"] = value(cfg.feature_brick);
+    gameplay["accelerate"] = value(cfg.accelerate);
+    gameplay["start_level"] = value(cfg.start_level as i64);
+    gameplay["level_lines"] = value(cfg.level_lines as i64);
+    gameplay["randomizer"] = value(cfg.randomizer.to_string());
+    gameplay["scoring"] = value(cfg.scoring.to_string());
+    gameplay["hold"] = value(cfg.hold);
+    gameplay["lock_delay"] = value(cfg.lock_delay.as_millis() as i64);
+    gameplay["lock_reset"] = value(cfg.lock_reset.to_string());
+    gameplay["lock_reset_limit"] = value(cfg.lock_reset_limit as i64);
+    doc["gameplay"] = Item::Table(gameplay);
+
+    let mut handling = Table::new();
+    handling["das"] = value(cfg.das.as_millis() as i64);
+    handling["arr"] = value(cfg.arr.as_millis() as i64);
+    handling["sdf"] = value(cfg.sdf);
+    let keys = KeysPreset::ALL
+        .into_iter()
+        .find(|&p| KeyBindings::preset(p) == cfg.keys);
+    handling["keys"] = value(keys.unwrap_or_default().to_string());
+    doc["handling"] = Item::Table(handling);
+
+    // 与预设相同时只写预设名, 否则在预设之上写出全部的值
+    let texture = TexturePreset::ALL
+        .into_iter()
+        .find(|&p| Texture::preset(p) == cfg.texture);
+    let theme = ThemePreset::ALL
+        .into_iter()
+        .find(|&p| Theme::preset(p) == cfg.theme);
+    let mut display = Table::new();
+    display["texture"] = match (texture, cfg.texture.compact) {
+        (Some(p), _) => value(p.to_string()),
+        (None, true) => value(TexturePreset::Compact.to_string()),
+        (None, false) => value(TexturePreset::Ascii.to_string()),
+    };
+    display["theme"] = value(theme.unwrap_or_default().to_string());
+    display["color"] = value(cfg.color.map_or("auto".to_string(), |c| c.to_string()));
+    doc["display"] = Item::Table(display);
+
+    if keys.is_none() {
+        let mut table = Table::new();
+        for control in Control::ALL {
+            let names: toml_edit::Array = cfg
+                .keys
+                .keys(control)
+                .iter()
+                .map(|k| k.to_string())
+                .collect();
+            table[&control.to_string()] = value(names);
+        }
+        doc["keys"] = Item::Table(table);
+    }
+    if texture.is_none() {
+        let mut table = Table::new();
+        table["full"] = value(&cfg.texture.full);
+        table["wall"] = value(&cfg.texture.wall);
+        table["empty"] = value(&cfg.texture.empty);
+        table["shadow"] = value(&cfg.texture.shadow);
+        doc["texture"] = Item::Table(table);
+    }
+    if theme.is_none() {
+        let name = |c: Option<Color>| c.map_or("none".to_string(), color_name);
+        let mut table = Table::new();
+        for (t, &c) in BrickType::iter().zip(&cfg.theme.pieces) {
+            table[&format!("{:?}", t).to_lowercase()] = value(color_name(c));
+        }
+        table["wall"] = value(name(cfg.theme.wall));
+        table["ghost"] = value(name(cfg.theme.ghost));
+        table["text"] = value(name(cfg.theme.text));
+        table["accent"] = value(name(cfg.theme.accent));
+        doc["colors"] = Item::Table(table);
+    }
+    doc.to_string()
+}
+
+#[test]
+fn config_test() {
+    let src = "[gameplay]\nmode = \"sprint\"\nwidth = 12\n\n[handling]\nsdf = inf\n\n[colors]\nt = \"#a000f0\"\n";
+    let cfg = parse(src, EnvConfig::default()).unwrap();
+    assert_eq!(cfg.mode, crate::mode::Mode::Sprint);
+    assert_eq!(cfg.width, 12);
+    assert!(cfg.sdf.is_infinite());
+    assert_eq!(
+        cfg.theme.piece(BrickType::T),
+        Color::TrueColor {
+            r: 0xa0,
+            g: 0,
+            b: 0xf0
+        }
+    );
+
+    // errors name the line and the key
+    let err = parse("[gameplay]\n\nwidth = \"wide\"\n", EnvConfig::default()).unwrap_err();
+    assert_eq!(err.to_string(), "3: gameplay.width should be a number");
+    let err = parse("[handling]\ndas = 100\nspeed = 1\n", EnvConfig::default()).unwrap_err();
+    assert_eq!(
+        err,
+        ConfigError::Setting {
+            line: Some(3),
+            setting: "handling.speed".to_string(),
+            reason: "is not a known setting".to_string()
+        }
+    );
+    let keys = parse(
+        "[handling]\nkeys = \"vim\"\n[keys]\nhold = [\"c\", \"tab\"]\n",
+        EnvConfig::default(),
+    )
+    .unwrap()
+    .keys;
+    assert_eq!(keys.keys(Control::Left), [Key::Char('h'), Key::Left]);
+    assert_eq!(keys.keys(Control::Hold), [Key::Char('c'), Key::Tab]);
+    let back = parse(
+        &to_toml(&EnvConfig {
+            keys: keys.clone(),
+            ..Default::default()
+        }),
+        EnvConfig::default(),
+    );
+    assert_eq!(back.unwrap().keys, keys);
+    // 指定的文件必须能读
+    let err = load(
+        Path::new("/nonexistent/tetris-rs.toml"),
+        EnvConfig::default(),
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err, ConfigError::File { error, .. } if matches!(*error, ConfigError::Read(_)))
+    );
+
+    // 数值合不合理等全部设置完再检查, 错误指回设置它的那一行
+    let err = |src| {
+        parse(src, EnvConfig::default())
+            .unwrap()
+            .validate()
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        err("[handling]\nsdf = 0\n"),
+        "line 2: sdf 0 should be a positive number (or inf)"
+    );
+    assert_eq!(
+        err("[keys]\n\nhold = \"x\"\n"),
+        "line 3: key x is bound to both rotate and hold"
+    );
+    assert_eq!(
+        parse("[gameplay\n", EnvConfig::default())
+            .unwrap_err()
+            .line(),
+        Some(1)
+    );
+
+    // what is written reads back the same
+    let back = parse(&to_toml(&cfg), EnvConfig::default()).unwrap();
+    assert_eq!(to_toml(&back), to_toml(&cfg));
+    assert_eq!(back.theme, cfg.theme);
+}
@@ -1,67 +1,705 @@
-use dotenv::dotenv;
-use std::env;
+use colored::Color;
+use std::{
+    collections::HashMap,
+    env, fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-#[derive(Debug, Clone, Copy)]
+use strum::IntoEnumIterator;
+
+use crate::{
+    bricks::{min_board_size, BrickType},
+    config,
+    keys::{parse_keys, Control, Key, KeyBindings, KeysPreset},
+    lock::LockReset,
+    mode::Mode,
+    randomizer::RandomizerKind,
+    scoring::ScoringKind,
+    texture::{Texture, TexturePreset},
+    theme::{parse_color, ColorDepth, Theme, ThemePreset},
+};
+
+#[derive(Debug, Clone)]
 pub struct EnvConfig {
+    pub mode: Mode,
     pub feature_brick: bool,
     pub accelerate: bool,
     pub width: usize,
     pub height: usize,
-    pub texture: [char; 4],
+    pub texture: Texture,
+    pub theme: Theme,
+    /// colors the terminal can show, None to detect from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub color: Option<ColorDepth>,
+    pub randomizer: RandomizerKind,
+    pub hold: bool,
+    pub lock_delay: Duration,
+    pub lock_reset: LockReset,
+    pub lock_reset_limit: usize,
+    /// level the game starts at (1-based)
+    pub start_level: usize,
+    /// cleared rows per level up
+    pub level_lines: usize,
+    pub scoring: ScoringKind,
+    /// Delayed Auto Shift: how long left/right must be held before auto-repeat starts
+    pub das: Duration,
+    /// Auto Repeat Rate: time between auto-repeated moves, zero slides to the wall at once
+    pub arr: Duration,
+    /// Soft Drop Factor: gravity multiplier while soft drop is held
+    pub sdf: f64,
+    pub keys: KeyBindings,
+    /// randomizer seed, None for a new one every game
+    pub seed: Option<u64>,
+    /// where settings like `gameplay.width` were set, see `source`
+    pub sources: HashMap<String, Source>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            mode: Mode::default(),
+            feature_brick: true,
+            accelerate: true,
+            width: 10,
+            height: 20,
+            texture: Texture::default(),
+            theme: Theme::default(),
+            color: None,
+            randomizer: RandomizerKind::default(),
+            hold: true,
+            lock_delay: Duration::from_millis(500),
+            lock_reset: LockReset::Move,
+            lock_reset_limit: 15,
+            start_level: 1,
+            level_lines: 10,
+            scoring: ScoringKind::default(),
+            das: Duration::from_millis(167),
+            arr: Duration::from_millis(33),
+            sdf: 20.0,
+            keys: KeyBindings::default(),
+            seed: None,
+            sources: HashMap::new(),
+        }
+    }
+}
+
+/// where a setting got its value, named by the errors of `EnvConfig::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// a line of a config file, path is None for configs not read from a file
+    File { path: Option<PathBuf>, line: usize },
+    /// an environment variable
+    Env(String),
+    /// a command line flag like `--width`
+    Flag(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File {
+                path: Some(path),
+                line,
+            } => write!(f, "{}:{}", path.display(), line),
+            Source::File { path: None, line } => write!(f, "line {}", line),
+            Source::Env(name) | Source::Flag(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// widest board accepted
+pub const MAX_WIDTH: usize = 100;
+/// highest board accepted
+pub const MAX_HEIGHT: usize = 100;
+/// highest level a game can start at, gravity stops growing there
+pub const MAX_START_LEVEL: usize = 20;
+
+/// why a config can't be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// the config file is not valid TOML
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    UnknownSection {
+        line: usize,
+        section: String,
+    },
+    /// a setting has a wrong value or is unknown: `reason` completes a sentence
+    /// like `gameplay.width should be a number`. line is None for environment variables.
+    Setting {
+        line: Option<usize>,
+        setting: String,
+        reason: String,
+    },
+    /// the config file can't be read, e.g. it doesn't exist
+    Read(String),
+    /// an error in the config file at `path`
+    File {
+        path: PathBuf,
+        error: Box<ConfigError>,
+    },
+    /// too narrow for the bricks to spawn, or absurdly wide.
+    /// `source` of this and the errors below is None for defaults and settings made in code
+    Width {
+        width: usize,
+        min: usize,
+        max: usize,
+        source: Option<Source>,
+    },
+    /// too low for the bricks to turn, or absurdly high
+    Height {
+        height: usize,
+        min: usize,
+        max: usize,
+        source: Option<Source>,
+    },
+    StartLevel {
+        level: usize,
+        source: Option<Source>,
+    },
+    /// levels would never go up
+    ZeroLevelLines {
+        source: Option<Source>,
+    },
+    /// soft drop would not fall
+    Sdf {
+        sdf: f64,
+        source: Option<Source>,
+    },
+    KeyConflict {
+        key: Key,
+        first: Control,
+        second: Control,
+        source: Option<Source>,
+    },
+}
+
+impl ConfigError {
+    /// the line of the config file the error is on
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ConfigError::Syntax { line, .. } | ConfigError::UnknownSection { line, .. } => {
+                Some(*line)
+            }
+            ConfigError::Setting { line, .. } => *line,
+            ConfigError::File { error, .. } => error.line(),
+            _ => None,
+        }
+    }
+
+    /// the same error with the line moved down by `offset`, for configs embedded in other files
+    pub fn shift_line(mut self, offset: usize) -> Self {
+        match &mut self {
+            ConfigError::Syntax { line, .. } | ConfigError::UnknownSection { line, .. } => {
+                *line += offset
+            }
+            ConfigError::Setting {
+                line: Some(line), ..
+            } => *line += offset,
+            ConfigError::File { error, .. } => **error = error.as_ref().clone().shift_line(offset),
+            _ => {}
+        }
+        self
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Syntax { line, message } => write!(f, "{}: {}", line, message),
+            ConfigError::UnknownSection { line, section } => {
+                write!(f, "{}: unknown section {}", line, section)
+            }
+            ConfigError::Setting {
+                line,
+                setting,
+                reason,
+            } => match line {
+                Some(line) => write!(f, "{}: {} {}", line, setting, reason),
+                None => write!(f, "{} {}", setting, reason),
+            },
+            ConfigError::Read(reason) => write!(f, "can't be read: {}", reason),
+            ConfigError::File { path, error } => match error.line() {
+                Some(_) => write!(f, "{}:{}", path.display(), error),
+                None => write!(f, "{}: {}", path.display(), error),
+            },
+            ConfigError::Width {
+                width,
+                min,
+                max,
+                source,
+            } => write!(
+                f,
+                "{}width {} should be between {} and {} for these bricks",
+                prefix(source),
+                width,
+                min,
+                max
+            ),
+            ConfigError::Height {
+                height,
+                min,
+                max,
+                source,
+            } => write!(
+                f,
+                "{}height {} should be between {} and {} for these bricks",
+                prefix(source),
+                height,
+                min,
+                max
+            ),
+            ConfigError::StartLevel { level, source } => write!(
+                f,
+                "{}start level {} should be between 1 and {}",
+                prefix(source),
+                level,
+                MAX_START_LEVEL
+            ),
+            ConfigError::ZeroLevelLines { source } => {
+                write!(f, "{}level lines should be at least 1", prefix(source))
+            }
+            ConfigError::Sdf { sdf, source } => write!(
+                f,
+                "{}sdf {} should be a positive number (or inf)",
+                prefix(source),
+                sdf
+            ),
+            ConfigError::KeyConflict {
+                key,
+                first,
+                second,
+                source,
+            } => write!(
+                f,
+                "{}key {} is bound to both {} and {}",
+                prefix(source),
+                key,
+                first,
+                second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// `source: ` in front of an error message, nothing for defaults
+fn prefix(source: &Option<Source>) -> String {
+    match source {
+        Some(source) => format!("{}: ", source),
+        None => String::new(),
+    }
 }
 
-pub fn load() -> Result<EnvConfig, String> {
-    dotenv().ok();
+/// an environment variable with a value that can't be used
+fn invalid(name: &str, reason: &str) -> ConfigError {
+    ConfigError::Setting {
+        line: None,
+        setting: name.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+impl EnvConfig {
+    /// remember that `setting`, named like in the config file, was last set by `source`
+    pub fn set_source(&mut self, setting: &str, source: Source) {
+        // 预设换掉了所有按键, 之前单独设置的按键不算了
+        if setting == "handling.keys" {
+            self.sources.retain(|s, _| !s.starts_with("keys."));
+        }
+        self.sources.insert(setting.to_string(), source);
+    }
+
+    /// where `setting` was last set, None when it is the default
+    pub fn source(&self, setting: &str) -> Option<&Source> {
+        self.sources.get(setting)
+    }
+
+    /// check the values fit together, e.g. that the board is large enough for the bricks.
+    /// games built from a config that fails this may panic.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let source = |setting: &str| self.source(setting).cloned();
+        let (min_width, min_height) = min_board_size(self.feature_brick);
+        if !(min_width..=MAX_WIDTH).contains(&self.width) {
+            return Err(ConfigError::Width {
+                width: self.width,
+                min: min_width,
+                max: MAX_WIDTH,
+                source: source("gameplay.width"),
+            });
+        }
+        if !(min_height..=MAX_HEIGHT).contains(&self.height) {
+            return Err(ConfigError::Height {
+                height: self.height,
+                min: min_height,
+                max: MAX_HEIGHT,
+                source: source("gameplay.height"),
+            });
+        }
+        if !(1..=MAX_START_LEVEL).contains(&self.start_level) {
+            return Err(ConfigError::StartLevel {
+                level: self.start_level,
+                source: source("gameplay.start_level"),
+            });
+        }
+        if self.level_lines == 0 {
+            return Err(ConfigError::ZeroLevelLines {
+                source: source("gameplay.level_lines"),
+            });
+        }
+        if self.sdf.is_nan() || self.sdf <= 0.0 {
+            return Err(ConfigError::Sdf {
+                sdf: self.sdf,
+                source: source("handling.sdf"),
+            });
+        }
+        self.keys.check().map_err(|e| match e {
+            ConfigError::KeyConflict {
+                key, first, second, ..
+            } => ConfigError::KeyConflict {
+                key,
+                first,
+                second,
+                // 冲突的两个操作里后设置的那个不知道是哪个, 先找后面的
+                source: source(&format!("keys.{}", second))
+                    .or_else(|| source(&format!("keys.{}", first)))
+                    .or_else(|| source("handling.keys")),
+            },
+            e => e,
+        })
+    }
+}
+
+/// the settings of the config file, then of environment variables
+/// (and `.env` with the `terminal` feature).
+/// not validated yet: apply the remaining overrides, then call `EnvConfig::validate`.
+pub fn load() -> Result<EnvConfig, ConfigError> {
+    load_file(None)
+}
+
+/// like `load` with the config file at `path` instead, which has to exist.
+/// None is the default config file, which may be missing.
+pub fn load_file(path: Option<&Path>) -> Result<EnvConfig, ConfigError> {
+    #[cfg(feature = "terminal")]
+    dotenv::dotenv().ok();
+    let base = match path {
+        Some(path) => config::load(path, EnvConfig::default())?,
+        None => config::load_default(EnvConfig::default())?,
+    };
+    from_env(base)
+}
 
+/// `base` with the environment variables that are set applied
+pub fn from_env(base: EnvConfig) -> Result<EnvConfig, ConfigError> {
+    let mode = match env::var("MODE") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("MODE", "should be one of marathon, sprint, ultra"))?,
+        Err(_) => base.mode,
+    };
     let feature_brick = match env::var("FEATURE_BRICK") {
         Ok(value) => value
             .parse()
-            .map_err(|_| "FEATURE_BRICK should be a bool")?,
-        Err(_) => true,
+            .map_err(|_| invalid("FEATURE_BRICK", "should be a bool"))?,
+        Err(_) => base.feature_brick,
     };
     let accelerate = match env::var("ACCELERATE_MODE") {
         Ok(value) => value
             .parse()
-            .map_err(|_| "ACCELERATE_MODE should be a bool")?,
-        Err(_) => true,
+            .map_err(|_| invalid("ACCELERATE_MODE", "should be a bool"))?,
+        Err(_) => base.accelerate,
     };
     let width = match env::var("WIDTH") {
-        Ok(value) => value.parse().map_err(|_| "WIDTH should be a number")?,
-        Err(_) => 10,
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("WIDTH", "should be a number"))?,
+        Err(_) => base.width,
     };
     let height = match env::var("HEIGHT") {
-        Ok(value) => value.parse().map_err(|_| "HEIGHT should be a number")?,
-        Err(_) => 20,
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("HEIGHT", "should be a number"))?,
+        Err(_) => base.height,
+    };
+    // 先选预设, 再用单独的变量覆盖其中的字符串
+    let mut texture = match env::var("TEXTURE") {
+        Ok(value) => Texture::preset(value.parse::<TexturePreset>().map_err(|_| {
+            invalid(
+                "TEXTURE",
+                "should be one of ascii, brackets, blocks, compact",
+            )
+        })?),
+        Err(_) => base.texture,
+    };
+    if let Ok(value) = env::var("TEXTURE_FULL") {
+        texture.full = value;
+    }
+    if let Ok(value) = env::var("TEXTURE_WALL") {
+        texture.wall = value;
+    }
+    if let Ok(value) = env::var("TEXTURE_EMPTY") {
+        texture.empty = value;
+    }
+    if let Ok(value) = env::var("TEXTURE_SHADOW") {
+        texture.shadow = value;
+    }
+
+    // 同样先选预设, 再覆盖单个颜色
+    let mut theme = match env::var("THEME") {
+        Ok(value) => Theme::preset(value.parse::<ThemePreset>().map_err(|_| {
+            invalid(
+                "THEME",
+                "should be one of classic, guideline, colorblind, contrast, mono",
+            )
+        })?),
+        Err(_) => base.theme,
+    };
+    let color = |name: &str| -> Result<Option<Color>, ConfigError> {
+        match env::var(name) {
+            Ok(value) => parse_color(&value)
+                .map(Some)
+                .ok_or_else(|| invalid(name, "should be a color name or #rrggbb")),
+            Err(_) => Ok(None),
+        }
+    };
+    for (i, brick_type) in BrickType::iter().enumerate() {
+        let name = format!("COLOR_{:?}", brick_type).to_uppercase();
+        if let Some(c) = color(&name)? {
+            theme.pieces[i] = c;
+        }
+    }
+    for (name, slot) in [
+        ("COLOR_WALL", &mut theme.wall),
+        ("COLOR_GHOST", &mut theme.ghost),
+        ("COLOR_TEXT", &mut theme.text),
+        ("COLOR_ACCENT", &mut theme.accent),
+    ] {
+        if let Some(c) = color(name)? {
+            *slot = Some(c);
+        }
+    }
+    let color =
+        match env::var("COLOR") {
+            Ok(value) if value == "auto" => None,
+            Ok(value) => Some(value.parse().map_err(|_| {
+                invalid("COLOR", "should be one of auto, truecolor, 256, 16, none")
+            })?),
+            Err(_) => base.color,
+        };
+
+    let randomizer = match env::var("RANDOMIZER") {
+        Ok(value) => value.parse().map_err(|_| {
+            invalid(
+                "RANDOMIZER",
+                "should be one of bag7, bag14, nes, tgm, random",
+            )
+        })?,
+        Err(_) => base.randomizer,
+    };
+
+    let hold = match env::var("HOLD") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("HOLD", "should be a bool"))?,
+        Err(_) => base.hold,
+    };
+
+    let lock_delay = match env::var("LOCK_DELAY") {
+        Ok(value) => Duration::from_millis(
+            value
+                .parse()
+                .map_err(|_| invalid("LOCK_DELAY", "should be a number of milliseconds"))?,
+        ),
+        Err(_) => base.lock_delay,
     };
-    let full = match env::var("TEXTURE_FULL") {
-        Ok(value) => value.parse().map_err(|_| "TEXTURE_FULL should be a char")?,
-        Err(_) => '#',
+
+    let lock_reset = match env::var("LOCK_RESET") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("LOCK_RESET", "should be one of move, step, none"))?,
+        Err(_) => base.lock_reset,
+    };
+
+    let lock_reset_limit = match env::var("LOCK_RESET_LIMIT") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("LOCK_RESET_LIMIT", "should be a number"))?,
+        Err(_) => base.lock_reset_limit,
+    };
+
+    let start_level = match env::var("START_LEVEL") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("START_LEVEL", "should be a number"))?,
+        Err(_) => base.start_level,
     };
 
-    let wall = match env::var("TEXTURE_WALL") {
-        Ok(value) => value.parse().map_err(|_| "TEXTURE_WALL should be a char")?,
-        Err(_) => 'H',
+    let level_lines = match env::var("LEVEL_LINES") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| invalid("LEVEL_LINES", "should be a number"))?,
+        Err(_) => base.level_lines,
     };
 
-    let empty = match env::var("TEXTURE_EMPTY") {
+    let scoring = match env::var("SCORING") {
         Ok(value) => value
             .parse()
-            .map_err(|_| "TEXTURE_EMPTY should be a char")?,
-        Err(_) => ' ',
+            .map_err(|_| invalid("SCORING", "should be one of classic, guideline"))?,
+        Err(_) => base.scoring,
+    };
+
+    let das = match env::var("DAS") {
+        Ok(value) => Duration::from_millis(
+            value
+                .parse()
+                .map_err(|_| invalid("DAS", "should be a number of milliseconds"))?,
+        ),
+        Err(_) => base.das,
+    };
+
+    let arr = match env::var("ARR") {
+        Ok(value) => Duration::from_millis(
+            value
+                .parse()
+                .map_err(|_| invalid("ARR", "should be a number of milliseconds"))?,
+        ),
+        Err(_) => base.arr,
     };
 
-    let shadow = match env::var("TEXTURE_SHADOW") {
+    let sdf = match env::var("SDF") {
         Ok(value) => value
             .parse()
-            .map_err(|_| "TEXTURE_SHADOW should be a char")?,
-        Err(_) => '.',
+            .map_err(|_| invalid("SDF", "should be a number (or inf)"))?,
+        Err(_) => base.sdf,
+    };
+
+    // 先选预设, 再覆盖单个操作的按键
+    let mut keys = match env::var("KEYS") {
+        Ok(value) => KeyBindings::preset(
+            value
+                .parse::<KeysPreset>()
+                .map_err(|_| invalid("KEYS", "should be one of default, vim, wasd"))?,
+        ),
+        Err(_) => base.keys,
     };
+    for control in Control::ALL {
+        let name = format!("KEY_{}", control).to_uppercase();
+        if let Ok(value) = env::var(&name) {
+            keys.set(
+                control,
+                parse_keys(&value).map_err(|e| invalid(&name, &format!("has an {}", e)))?,
+            );
+        }
+    }
 
-    Ok(EnvConfig {
+    let mut cfg = EnvConfig {
+        mode,
         feature_brick,
         accelerate,
         width,
         height,
-        texture: [full, wall, empty, shadow],
-    })
+        texture,
+        theme,
+        color,
+        randomizer,
+        hold,
+        lock_delay,
+        lock_reset,
+        lock_reset_limit,
+        start_level,
+        level_lines,
+        scoring,
+        das,
+        arr,
+        sdf,
+        keys,
+        seed: base.seed,
+        sources: base.sources,
+    };
+    // 预设在单个按键之前
+    let mut settings: Vec<(String, String)> = [
+        ("gameplay.width", "WIDTH"),
+        ("gameplay.height", "HEIGHT"),
+        ("gameplay.start_level", "START_LEVEL"),
+        ("gameplay.level_lines", "LEVEL_LINES"),
+        ("handling.sdf", "SDF"),
+        ("handling.keys", "KEYS"),
+    ]
+    .map(|(setting, name)| (setting.to_string(), name.to_string()))
+    .into();
+    for control in Control::ALL {
+        let name = format!("KEY_{}", control).to_uppercase();
+        settings.push((format!("keys.{}", control), name));
+    }
+    for (setting, name) in settings {
+        if env::var_os(&name).is_some() {
+            cfg.set_source(&setting, Source::Env(name));
+        }
+    }
+    Ok(cfg)
+}
+
+#[test]
+fn validate_test() {
+    use crate::game::Tetris;
+
+    assert_eq!(EnvConfig::default().validate(), Ok(()));
+    let cfg = EnvConfig {
+        width: 0,
+        ..Default::default()
+    };
+    assert_eq!(
+        cfg.validate(),
+        Err(ConfigError::Width {
+            width: 0,
+            min: 4,
+            max: MAX_WIDTH,
+            source: None
+        })
+    );
+    let cfg = EnvConfig {
+        height: 1,
+        ..Default::default()
+    };
+    assert!(matches!(cfg.validate(), Err(ConfigError::Height { .. })));
+    let cfg = EnvConfig {
+        sdf: f64::NAN,
+        ..Default::default()
+    };
+    assert!(matches!(cfg.validate(), Err(ConfigError::Sdf { .. })));
+
+    // 文件里太矮的棋盘可以被之后的设置(比如 --height)盖掉, 读取时不检查
+    let path = env::temp_dir().join(format!("tetris-rs-validate-{}.toml", std::process::id()));
+    std::fs::write(&path, "[gameplay]\nheight = 2\n").unwrap();
+    let mut cfg = config::load(&path, EnvConfig::default()).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(
+        cfg.validate().unwrap_err().to_string(),
+        format!(
+            "{}:2: height 2 should be between 4 and 100 for these bricks",
+            path.display()
+        )
+    );
+    cfg.height = 20;
+    assert_eq!(cfg.validate(), Ok(()));
+
+    // the smallest board still plays until it tops out
+    let mut t = Tetris::new(EnvConfig {
+        width: 4,
+        height: 4,
+        seed: Some(3),
+        ..Default::default()
+    });
+    t.start();
+    for i in 0..100 {
+        match i % 3 {
+            0 => t.event_rotate(),
+            1 => t.event_right(),
+            _ => t.event_sink(),
+        }
+    }
+    assert!(t.is_over());
 }
@@ -1,8 +1,24 @@
 use colored::Color;
 
-use std::{collections::VecDeque, vec};
+use std::{collections::VecDeque, time::Duration, vec};
 
-use crate::{bricks::*, env::EnvConfig, record::Record};
+use crate::{
+    bricks::*,
+    env::EnvConfig,
+    handling::{Handling, Input, Shift},
+    lock::LockDelay,
+    randomizer::Randomizer,
+    record::Record,
+    rotation::{kicks, Turn},
+    scoring::Scoring,
+    spin::{self, LineClear, TSpin},
+};
+
+pub const FRAMES_PER_SECOND: u32 = 60;
+/// length of one simulation frame
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND as u64);
+/// longest `dt` a single `tick` will simulate
+const MAX_TICK: Duration = Duration::from_millis(250);
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum GameStatus {
@@ -25,45 +41,105 @@ pub enum ControlLimit {
     CantLeftAndRight,
 }
 
-
-
 #[derive(Debug, Clone)]
 pub struct Tetris {
     pub board: Board,
     pub status: GameStatus,
     pub now_brick: Option<Brick>,
-    pub now_brick_position: (usize, usize),
+    /// board position (x right, y down) of the brick origin, may be outside the board
+    pub now_brick_position: (isize, isize),
     pub following_bricks: VecDeque<Brick>,
     pub record: Record,
     pub cfg: EnvConfig,
+    pub randomizer: Box<dyn Randomizer>,
+    pub scoring: Box<dyn Scoring>,
+    pub handling: Handling,
+    pub hold_brick: Option<Brick>,
+    /// false after a hold until the next brick locks
+    pub can_hold: bool,
+    pub lock: LockDelay,
+    /// frames simulated since the game started
+    pub frames: u64,
+    /// turn and kick index of the last successful action if it was a rotation, None after a move
+    pub last_kick: Option<(Turn, usize)>,
+    /// clear type of the last notable lock and the frame it happened, for the UI
+    pub last_clear: Option<(LineClear, u64)>,
+    /// seed of the randomizer, the same seed and inputs replay the same game.
+    /// None for games dealing from a custom `Randomizer`, whose seed is unknown.
+    pub seed: Option<u64>,
+    /// a custom randomizer as it was before dealing, `reset` deals from it again.
+    /// None for games built from `cfg.randomizer`
+    custom_randomizer: Option<Box<dyn Randomizer>>,
+    accumulator: Duration,
+    gravity_progress: f64,
 }
 
 impl Tetris {
     pub fn new(cfg: EnvConfig) -> Self {
+        let seed = cfg.seed.unwrap_or_else(rand::random);
+        let randomizer = cfg.randomizer.build(cfg.feature_brick, Some(seed));
+        let mut t = Self::with_randomizer(cfg, randomizer);
+        t.seed = Some(seed);
+        t.custom_randomizer = None;
+        t
+    }
+
+    /// start over with an empty board and a new record, rebuilding the randomizer
+    /// and scoring ruleset from `cfg` (which may be changed before resetting).
+    /// a randomizer from `with_randomizer` is kept and deals its bricks from the start again.
+    /// handling settings like `release_timeout` are kept. call `start()` to play again.
+    pub fn reset(&mut self) {
+        let mut handling = self.handling.clone();
+        handling.release_all();
+        *self = match self.custom_randomizer.take() {
+            Some(randomizer) => Self::with_randomizer(self.cfg.clone(), randomizer),
+            None => Self::new(self.cfg.clone()),
+        };
+        self.handling = handling;
+    }
+
+    /// new game dealing bricks from a custom generator
+    pub fn with_randomizer(cfg: EnvConfig, mut randomizer: Box<dyn Randomizer>) -> Self {
+        let custom_randomizer = Some(randomizer.clone());
         let w = cfg.width;
         let h = cfg.height;
         let mut q = VecDeque::new();
         for _ in 0..3 {
-            q.push_back(Brick::random(cfg.feature_brick));
+            q.push_back(cfg.theme.brick(randomizer.next()));
         }
         let board = Board::new(w, h);
         let c = board.center;
+        let mut record = Record::new();
+        record.level = cfg.start_level;
         Self {
             board,
+            scoring: cfg.scoring.build(),
+            seed: None,
             cfg,
             status: GameStatus::Pause,
-            now_brick_position: (c, 0),
+            now_brick_position: (c as isize, 0),
             following_bricks: q,
             now_brick: None,
-            record: Record::new(),
+            record,
+            randomizer,
+            handling: Handling::default(),
+            hold_brick: None,
+            can_hold: true,
+            lock: LockDelay::new(0),
+            frames: 0,
+            last_kick: None,
+            last_clear: None,
+            custom_randomizer,
+            accumulator: Duration::ZERO,
+            gravity_progress: 0.0,
         }
     }
 
     pub fn get_shadow(&self) -> Vec<(isize, isize)> {
         let mut now_poss = self.get_absolute();
         while self.try_collapse(now_poss.clone()).is_none() {
-            for i in 0..now_poss.len() {
-                now_poss[i].1 += 1;
+            for pos in now_poss.iter_mut() {
+                pos.1 += 1;
             }
         }
         now_poss
@@ -76,7 +152,7 @@ impl Tetris {
     // instance method
     fn add_next_brick(&mut self) {
         self.following_bricks
-            .push_back(Brick::random(self.cfg.feature_brick))
+            .push_back(self.cfg.theme.brick(self.randomizer.next()))
     }
 
     fn combout(&mut self) -> usize {
@@ -94,17 +170,56 @@ impl Tetris {
         }
         combo_count
     }
+    /// whether `brick` fits with its origin at `pos`: inside the walls, above the floor
+    /// and not overlapping the stack. cells above the board are allowed.
+    pub fn fits(&self, brick: &Brick, pos: (isize, isize)) -> bool {
+        brick.pixels_info(pos.0, pos.1).iter().all(|&(x, y)| {
+            x >= 0
+                && x < self.board.width as isize
+                && y < self.board.height as isize
+                && (y < 0 || self.board.datas[y as usize][x as usize].0.is_none())
+        })
+    }
+
+    /// SRS rotation: try each kick offset of the brick's table in order and
+    /// keep the first one that fits. returns false (and changes nothing) if none fit.
     pub fn try_rotate(&mut self) -> bool {
-        if let Some(brick) = &mut self.now_brick {
-            let old = brick.clone();
-            brick.rotate();
-            if !self.is_legal_positions() || self.is_overlapped() {
-                self.now_brick = Some(old); //还原
-                return false;
+        self.try_turn(Turn::Cw)
+    }
+
+    pub fn try_rotate_ccw(&mut self) -> bool {
+        self.try_turn(Turn::Ccw)
+    }
+
+    pub fn try_rotate_180(&mut self) -> bool {
+        self.try_turn(Turn::Half)
+    }
+
+    fn try_turn(&mut self, turn: Turn) -> bool {
+        let brick = match &self.now_brick {
+            Some(b) => b,
+            None => return false,
+        };
+        let mut rotated = brick.clone();
+        rotated.turn(turn);
+        let (x, y) = self.now_brick_position;
+        let offsets = kicks(
+            brick.brick_type.kick_table(),
+            brick.rotation,
+            rotated.rotation,
+        );
+        for (i, &(dx, dy)) in offsets.iter().enumerate() {
+            // kick offsets are y-up, the board is y-down
+            let pos = (x + dx, y - dy);
+            if self.fits(&rotated, pos) {
+                self.now_brick = Some(rotated);
+                self.now_brick_position = pos;
+                self.last_kick = Some((turn, i));
+                self.on_action();
+                return true;
             }
-            return true;
         }
-        return false;
+        false
     }
     // is_legal_positions 是否越界
     pub fn is_legal_positions(&self) -> bool {
@@ -125,10 +240,8 @@ impl Tetris {
         // 只有旋转需要重叠检验。左右移动使用limits检验，下落使用collapse检验.
         for (x, y) in self.get_absolute() {
             // 不考虑负y
-            if y >= 0 {
-                if self.board.datas[y as usize][x as usize].0.is_some() {
-                    return true;
-                }
+            if y >= 0 && self.board.datas[y as usize][x as usize].0.is_some() {
+                return true;
             }
         }
         false
@@ -136,53 +249,168 @@ impl Tetris {
 
     // -----------------EVENT--------------------
     pub fn event_rotate(&mut self) {
+        if !self.is_running() {
+            return;
+        }
         self.try_rotate();
     }
 
-    pub fn event_left(&mut self) {
-        match self.limited() {
-            Some(limit) => match limit {
-                ControlLimit::CantLeft => return,
-                ControlLimit::CantLeftAndRight => return,
-                ControlLimit::CantRight => {}
-            },
-            None => {}
+    pub fn event_rotate_ccw(&mut self) {
+        if !self.is_running() {
+            return;
         }
+        self.try_rotate_ccw();
+    }
+
+    pub fn event_rotate_180(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        self.try_rotate_180();
+    }
 
-        self.now_brick_position.0 -= 1;
+    pub fn event_left(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        self.try_shift(Input::Left);
     }
 
     pub fn event_right(&mut self) {
-        match self.limited() {
-            Some(limit) => match limit {
-                ControlLimit::CantRight => return,
-                ControlLimit::CantLeftAndRight => return,
-                ControlLimit::CantLeft => {}
-            },
-            None => {}
+        if !self.is_running() {
+            return;
+        }
+        self.try_shift(Input::Right);
+    }
+
+    /// key-down of a held input: acts once right away, then DAS/ARR (or the soft drop
+    /// factor) take over every frame until `release`. repeats of a held key are ignored.
+    pub fn press(&mut self, input: Input) {
+        if !self.is_running() {
+            return;
+        }
+        if !self.handling.press(input) {
+            return;
+        }
+        match input {
+            Input::Left => self.event_left(),
+            Input::Right => self.event_right(),
+            Input::SoftDrop => self.event_accelerate(),
+        }
+    }
+
+    pub fn release(&mut self, input: Input) {
+        self.handling.release(input);
+    }
+    /// swap the falling brick with the hold slot (or the next brick if the slot is empty).
+    /// allowed once per drop: the next hold needs a brick to lock first.
+    pub fn event_hold(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        if !self.cfg.hold || !self.can_hold {
+            return;
+        }
+        let current = match self.now_brick.take() {
+            Some(b) => b,
+            None => return,
+        };
+        // 放入暂存区的砖块回到初始方向
+        match self
+            .hold_brick
+            .replace(self.cfg.theme.brick(current.brick_type))
+        {
+            Some(held) => self.spawn(held),
+            None => self.new_small_run(),
+        }
+        self.can_hold = false;
+    }
+
+    /// pause a running game or resume a paused one
+    pub fn event_pause(&mut self) {
+        match self.status {
+            GameStatus::Running => self.pause(),
+            GameStatus::Pause => self.resume(),
+            _ => {}
+        }
+    }
+
+    /// freeze gravity, lock delay and auto-shift. held keys are released.
+    pub fn pause(&mut self) {
+        if self.status == GameStatus::Running {
+            self.status = GameStatus::Pause;
+            self.handling.release_all();
         }
-        self.now_brick_position.0 += 1;
     }
+
+    pub fn resume(&mut self) {
+        // 还没开始的游戏不能恢复
+        if self.status == GameStatus::Pause && self.now_brick.is_some() {
+            self.status = GameStatus::Running;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == GameStatus::Running
+    }
+
+    /// paused after `start`, as opposed to not started yet
+    pub fn is_paused(&self) -> bool {
+        self.status == GameStatus::Pause && self.now_brick.is_some()
+    }
+
+    /// topped out, quit or finished the goal of the mode
+    pub fn is_over(&self) -> bool {
+        matches!(self.status, GameStatus::Exit(_))
+    }
+
     pub fn event_quit(&mut self) {
         self.status = GameStatus::Exit("keyboard quit".to_string());
     }
 
     pub fn event_sink(&mut self) {
+        if !self.is_running() {
+            return;
+        }
         // 持续掉掉落
         // 这里不需要担心内部的游戏结束触发。机制。如果结束，则游戏Status成为Exit，游戏循环内通过判断则结束游戏。
         let mut coounter = 0;
         while self.down_settle() == InGameStatus::KeepDropping {
             coounter += 1;
         }
-        self.record.score += coounter;
+        self.record.score += self.scoring.hard_drop(coounter);
     }
 
     pub fn event_accelerate(&mut self) {
-        self.down_settle();
-        self.record.score += 1;
+        if !self.is_running() {
+            return;
+        }
+        if self.gravity_step() {
+            self.record.score += self.scoring.soft_drop(1);
+        }
     }
     // ---------------EVENT END--------------------
 
+    /// move one cell left or right. returns false if blocked.
+    fn try_shift(&mut self, dir: Input) -> bool {
+        let limit = self.limited();
+        let dx = match (dir, limit) {
+            (_, Some(ControlLimit::CantLeftAndRight)) => return false,
+            (Input::Left, Some(ControlLimit::CantLeft)) => return false,
+            (Input::Right, Some(ControlLimit::CantRight)) => return false,
+            (Input::Left, _) => -1,
+            (Input::Right, _) => 1,
+            (Input::SoftDrop, _) => return false,
+        };
+        if self.now_brick.is_none() {
+            return false;
+        }
+        self.now_brick_position.0 += dx;
+        self.last_kick = None;
+        self.on_action();
+        true
+    }
+
     pub fn limited(&self) -> Option<ControlLimit> {
         //是否贴着左右的Unit 用于限制左右移动碰撞箱
         let absolute_positions = self.get_absolute();
@@ -191,15 +419,22 @@ impl Tetris {
         let mut cant_r = false;
         for e in &absolute_positions {
             let &(x, y) = e;
+            // 墙壁 (棋盘上方的格子也要检查)
+            if x == 0 {
+                cant_l = true
+            }
+            if x == self.board.width as isize - 1 {
+                cant_r = true
+            }
             //防止越界
             if y >= 0 {
                 // 左边
-                if x == 0 || self.board.datas[y as usize][x as usize - 1].0.is_some() {
+                if x > 0 && self.board.datas[y as usize][x as usize - 1].0.is_some() {
                     cant_l = true
                 }
                 // 右边
-                if x == (self.board.width - 1) as isize
-                    || self.board.datas[y as usize][x as usize + 1].0.is_some()
+                if x < self.board.width as isize - 1
+                    && self.board.datas[y as usize][x as usize + 1].0.is_some()
                 {
                     cant_r = true
                 }
@@ -215,10 +450,7 @@ impl Tetris {
 
     pub fn get_absolute(&self) -> Vec<(isize, isize)> {
         match self.now_brick.as_ref() {
-            Some(e) => e.pixels_info(
-                self.now_brick_position.0 as isize,
-                self.now_brick_position.1 as isize,
-            ),
+            Some(e) => e.pixels_info(self.now_brick_position.0, self.now_brick_position.1),
             None => vec![],
         }
     }
@@ -238,14 +470,15 @@ impl Tetris {
         let mut can_collapse = false;
         for e in &poss {
             let &(x, y) = e;
-            if y >= 0 {
+            // 棋盘上方一行的格子也可能压在第0行上
+            if y >= -1 {
                 if y == self.board.height as isize - 1 {
                     // 碰到地板
                     can_collapse = true;
                     break;
                 }
 
-                if self.board.datas[y as usize + 1][x as usize].0.is_some() {
+                if self.board.datas[(y + 1) as usize][x as usize].0.is_some() {
                     // 碰到了实体方块
                     can_collapse = true;
                     break;
@@ -253,9 +486,9 @@ impl Tetris {
             }
         }
         if can_collapse {
-            return Some(poss);
+            Some(poss)
         } else {
-            return None;
+            None
         }
     }
 
@@ -274,7 +507,7 @@ impl Tetris {
             // 这里是完成💥（碰撞）同时还没有游戏结束。
             return InGameStatus::FinishDropping;
         }
-        return InGameStatus::KeepDropping;
+        InGameStatus::KeepDropping
     }
 
     // 结算
@@ -283,12 +516,23 @@ impl Tetris {
         let down_result = self.try_down();
         match down_result {
             InGameStatus::FinishDropping => {
+                let tspin = self.detect_tspin(); // 消除之前判断
                 let times = self.combout(); //计算消除的行数
-                self.record.compute(times); //记录对应的分数
+                let clear = LineClear {
+                    lines: times,
+                    tspin,
+                    perfect: times > 0 && self.board.is_empty(),
+                };
+                self.scoring.score_clear(&mut self.record, clear); //记录对应的分数
+                if clear.is_notable() {
+                    self.last_clear = Some((clear, self.frames));
+                }
+                self.update_level();
                 self.new_small_run(); //召唤新的砖块.
             }
             InGameStatus::KeepDropping => {
                 self.now_brick_position.1 += 1;
+                self.last_kick = None;
             }
             InGameStatus::GameJustOver => {}
         }
@@ -297,10 +541,18 @@ impl Tetris {
 
     fn new_small_run(&mut self) {
         let new_brick = self.following_bricks.pop_front().unwrap();
-        self.now_brick = Some(new_brick);
         self.add_next_brick();
         //开始第二个
-        self.now_brick_position = (self.board.center, 0);
+        self.spawn(new_brick);
+        // 上一块已经落地, 可以再次暂存
+        self.can_hold = true;
+    }
+
+    fn spawn(&mut self, brick: Brick) {
+        self.now_brick = Some(brick);
+        self.now_brick_position = (self.board.center as isize, 0);
+        self.lock = LockDelay::new(self.now_brick_position.1);
+        self.last_kick = None;
         // 计算是否重叠，否则直接结束游戏.
         if self.is_overlapped() {
             self.status = GameStatus::Exit("overlap".to_string());
@@ -312,33 +564,149 @@ impl Tetris {
         self.new_small_run();
     }
     pub fn update(&mut self) {
+        self.gravity_step();
+    }
+
+    /// 3-corner T-spin check for the brick that is locking. only a T whose last
+    /// successful action was a rotation counts.
+    fn detect_tspin(&self) -> TSpin {
+        let (brick, (turn, kick)) = match (&self.now_brick, self.last_kick) {
+            (Some(b), Some(k)) if b.brick_type == BrickType::T => (b, k),
+            _ => return TSpin::None,
+        };
+        let (cx, cy) = self.now_brick_position;
+        spin::classify(brick.rotation, turn, kick, |(dx, dy)| {
+            let (x, y) = (cx + dx, cy - dy);
+            x < 0
+                || x >= self.board.width as isize
+                || y >= self.board.height as isize
+                || (y >= 0 && self.board.datas[y as usize][x as usize].0.is_some())
+        })
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.try_collapse(self.get_absolute()).is_some()
+    }
+
+    /// move down one row. a grounded brick is left to the lock delay, or locked
+    /// right away when there is none. returns true if the brick moved.
+    fn gravity_step(&mut self) -> bool {
+        if self.now_brick.is_none() {
+            return false;
+        }
+        if self.is_grounded() {
+            if self.cfg.lock_delay.is_zero() {
+                self.down_settle();
+            }
+            return false;
+        }
         self.down_settle();
+        self.lock
+            .on_fall(self.cfg.lock_reset, self.now_brick_position.1);
+        true
     }
 
-    pub fn update_by(&mut self, counter: i32) {
-        match self.cfg.accelerate {
-            true => {
-                let time = match self.record.score {
-                    0..=5999 => 100,
-                    6000..=9999 => 70,
-                    10000..=24999 => 60,
-                    25000..=39999 => 50,
-                    40000..=59999 => 45,
-                    _ => 40,
-                };
-                if counter % (time) == 0 {
-                    self.update()
+    fn on_action(&mut self) {
+        let grounded = self.is_grounded();
+        self.lock
+            .on_action(self.cfg.lock_reset, self.cfg.lock_reset_limit, grounded);
+    }
+
+    /// run the lock delay for `elapsed`, locking the brick once it runs out.
+    pub fn update_lock(&mut self, elapsed: Duration) {
+        if self.now_brick.is_none() || self.cfg.lock_delay.is_zero() || !self.is_grounded() {
+            return;
+        }
+        if self.lock.tick(
+            elapsed,
+            self.cfg.lock_delay,
+            self.cfg.lock_reset,
+            self.cfg.lock_reset_limit,
+        ) {
+            self.down_settle();
+        }
+    }
+
+    /// advance the simulation by `dt` of wall-clock time, in fixed `FRAME` steps.
+    /// leftover time is carried over to the next call. returns the number of frames run.
+    pub fn tick(&mut self, dt: Duration) -> u32 {
+        // 防止卡顿后一次性追赶太多帧
+        self.accumulator += dt.min(MAX_TICK);
+        let mut frames = 0;
+        while self.accumulator >= FRAME {
+            self.accumulator -= FRAME;
+            self.frame();
+            frames += 1;
+        }
+        frames
+    }
+
+    /// one fixed-timestep frame: gravity then lock delay
+    fn frame(&mut self) {
+        if self.status != GameStatus::Running || self.now_brick.is_none() {
+            return;
+        }
+        if self.cfg.mode.is_complete(&self.record, self.frames) {
+            self.status = GameStatus::Exit(format!("{} complete", self.cfg.mode));
+            return;
+        }
+        self.frames += 1;
+        match self.handling.frame(FRAME, self.cfg.das, self.cfg.arr) {
+            Shift::None => {}
+            Shift::Cells(dir, n) => {
+                for _ in 0..n {
+                    self.try_shift(dir);
                 }
             }
-            false => {
-                if counter % (100) == 0 {
-                    self.update()
-                }
+            Shift::Wall(dir) => while self.try_shift(dir) {},
+        }
+        // 按住软降时重力乘以 SDF
+        let soft_drop = self.handling.is_down(Input::SoftDrop);
+        let gravity = match soft_drop {
+            true => (self.gravity() * self.cfg.sdf).min(MAX_GRAVITY),
+            false => self.gravity(),
+        };
+        self.gravity_progress += gravity;
+        // 浮点累加误差: 60 次 1/60 可能略小于 1
+        while self.gravity_progress >= 1.0 - 1e-9 {
+            self.gravity_progress = (self.gravity_progress - 1.0).max(0.0);
+            if !self.gravity_step() {
+                // 落地或锁定, 剩余的重力作废
+                self.gravity_progress = 0.0;
+                break;
+            }
+            if soft_drop {
+                self.record.score += self.scoring.soft_drop(1);
             }
         }
+        self.update_lock(FRAME);
+    }
+
+    /// current gravity in G (cells per frame), from the level
+    pub fn gravity(&self) -> f64 {
+        level_gravity(self.record.level)
+    }
+
+    /// level goes up every `level_lines` cleared rows, unless the level is fixed
+    fn update_level(&mut self) {
+        if self.cfg.accelerate {
+            self.record.level =
+                self.cfg.start_level + self.record.eliminate_rows / self.cfg.level_lines.max(1);
+        }
     }
 }
 
+/// highest gravity: the brick reaches the floor in the frame it spawns
+pub const MAX_GRAVITY: f64 = 20.0;
+
+/// guideline gravity curve in G: `(0.8 - (level - 1) * 0.007) ^ (level - 1)` seconds per row,
+/// capped at 20G (reached around level 19).
+pub fn level_gravity(level: usize) -> f64 {
+    let l = (level.clamp(1, 20) - 1) as f64;
+    let seconds_per_row = (0.8 - l * 0.007).powf(l);
+    (1.0 / (seconds_per_row * FRAMES_PER_SECOND as f64)).min(MAX_GRAVITY)
+}
+
 #[derive(Debug, Clone)]
 pub struct Unit(pub Option<Color>);
 pub type Line = Vec<Unit>;
@@ -360,7 +728,338 @@ impl Board {
             width,
             height,
             datas,
-            center: width / 2,
+            // spawn column, rounded to the left like the guideline
+            center: width.saturating_sub(1) / 2,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.datas.iter().flatten().all(|u| u.0.is_none())
+    }
+}
+
+#[test]
+fn headless_game_test() {
+    // 不依赖终端, 一直硬降直到游戏结束
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    for _ in 0..1000 {
+        if let GameStatus::Exit(_) = t.status {
+            break;
+        }
+        t.event_sink();
+    }
+    assert!(matches!(t.status, GameStatus::Exit(_)));
+    assert!(t.record.score > 0);
+}
+
+#[test]
+fn wall_kick_test() {
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    t.now_brick = Some(Brick::new(BrickType::I));
+    t.now_brick_position = (5, 10);
+    // vertical I against the right wall
+    assert!(t.try_rotate());
+    while t.now_brick_position.0 < 8 {
+        t.event_right();
+    }
+    assert_eq!(t.get_absolute().iter().map(|p| p.0).max(), Some(9));
+    // R -> 2 needs a kick to stay inside
+    assert!(t.try_rotate());
+    assert!(t.get_absolute().iter().all(|&(x, _)| (0..10).contains(&x)));
+
+    // T pointing right against the left wall kicks one cell right on R -> 2
+    let mut t = Tetris::new(EnvConfig::default());
+    let mut brick = Brick::new(BrickType::T);
+    brick.rotate();
+    t.now_brick = Some(brick);
+    t.now_brick_position = (0, 10);
+    assert!(t.try_rotate());
+    assert_eq!(t.now_brick_position, (1, 10));
+
+    // and back the other way: 2 -> R is the inverse kick
+    assert!(t.try_rotate_ccw());
+    assert_eq!(
+        t.now_brick.as_ref().unwrap().rotation,
+        crate::rotation::Rotation::Right
+    );
+    assert!(t.try_rotate_180());
+    assert_eq!(
+        t.now_brick.as_ref().unwrap().rotation,
+        crate::rotation::Rotation::Left
+    );
+}
+
+#[test]
+fn hold_test() {
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    let first = t.now_brick.as_ref().unwrap().brick_type;
+    let next = t.following_bricks[0].brick_type;
+    t.event_rotate();
+    t.event_left();
+    t.event_hold();
+    assert_eq!(t.hold_brick.as_ref().unwrap().brick_type, first);
+    assert_eq!(
+        t.hold_brick.as_ref().unwrap().pixels,
+        Brick::new(first).pixels
+    );
+    assert_eq!(t.now_brick.as_ref().unwrap().brick_type, next);
+
+    // only once per drop
+    t.event_hold();
+    assert_eq!(t.now_brick.as_ref().unwrap().brick_type, next);
+
+    // after locking the held brick comes back at the spawn position
+    t.event_sink();
+    t.event_hold();
+    assert_eq!(t.now_brick.as_ref().unwrap().brick_type, first);
+    assert_eq!(t.now_brick_position, (t.board.center as isize, 0));
+
+    let mut t = Tetris::new(EnvConfig {
+        hold: false,
+        ..Default::default()
+    });
+    t.start();
+    t.event_hold();
+    assert!(t.hold_brick.is_none());
+}
+
+#[test]
+fn lock_delay_game_test() {
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    while !t.is_grounded() {
+        t.update();
+    }
+    let first = t.now_brick_position;
+    // gravity does not lock a grounded brick
+    t.update();
+    assert_eq!(t.now_brick_position, first);
+    // sliding resets the timer
+    t.update_lock(Duration::from_millis(400));
+    t.event_left();
+    t.update_lock(Duration::from_millis(400));
+    assert_eq!(t.now_brick_position.1, first.1);
+    assert!(t.board.datas.iter().flatten().all(|u| u.0.is_none()));
+    // until it runs out
+    t.update_lock(Duration::from_millis(100));
+    assert!(t.board.datas.iter().flatten().any(|u| u.0.is_some()));
+
+    // moving in the air leaves every reset for after landing
+    let mut t = Tetris::new(EnvConfig {
+        lock_reset_limit: 3,
+        ..Default::default()
+    });
+    t.start();
+    for _ in 0..10 {
+        t.event_right();
+        t.event_left();
+    }
+    while !t.is_grounded() {
+        t.update();
+    }
+    assert_eq!(t.lock.resets, 0);
+    t.update_lock(Duration::ZERO);
+    assert!(t.board.is_empty());
+    t.event_left();
+    assert_eq!(t.lock.resets, 1);
+
+    // no lock delay: locks on the next gravity tick like before
+    let mut t = Tetris::new(EnvConfig {
+        lock_delay: Duration::ZERO,
+        ..Default::default()
+    });
+    t.start();
+    while !t.is_grounded() {
+        t.update();
+    }
+    t.update();
+    assert!(t.board.datas.iter().flatten().any(|u| u.0.is_some()));
+}
+
+#[test]
+fn tick_test() {
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    // one row per second at the start
+    assert_eq!(t.tick(Duration::from_millis(200)), 12);
+    for _ in 0..3 {
+        t.tick(Duration::from_millis(200));
+    }
+    assert_eq!(t.frames, 48);
+    assert_eq!(t.now_brick_position.1, 0);
+    t.tick(Duration::from_millis(200));
+    assert_eq!(t.frames, 60);
+    assert_eq!(t.now_brick_position.1, 1);
+    // a single tick never simulates more than `MAX_TICK`
+    assert_eq!(t.tick(Duration::from_secs(10)), 15);
+
+    // the leftover of a tick is carried to the next one
+    let mut a = Tetris::new(EnvConfig::default());
+    a.start();
+    for _ in 0..100 {
+        a.tick(Duration::from_millis(7));
+    }
+    assert_eq!(a.frames, 42);
+
+    // 20G: on the ground after a single frame
+    t.gravity_progress = 19.0;
+    t.tick(FRAME);
+    assert!(t.is_grounded());
+}
+
+#[test]
+fn level_test() {
+    assert!((level_gravity(1) - 1.0 / 60.0).abs() < 1e-9);
+    assert!(level_gravity(2) > level_gravity(1));
+    assert_eq!(level_gravity(20), MAX_GRAVITY);
+    assert_eq!(level_gravity(999), MAX_GRAVITY);
+
+    let mut t = Tetris::new(EnvConfig {
+        start_level: 3,
+        level_lines: 2,
+        ..Default::default()
+    });
+    assert_eq!(t.record.level, 3);
+    t.record.eliminate_rows = 5;
+    t.update_level();
+    assert_eq!(t.record.level, 5);
+
+    // fixed level
+    t.cfg.accelerate = false;
+    t.record.eliminate_rows = 50;
+    t.update_level();
+    assert_eq!(t.record.level, 5);
+}
+
+#[test]
+fn tspin_test() {
+    // T-spin slot, the T spins from pointing up to pointing right:
+    // ...#......
+    // #...######
+    // ##.#######
+    let mut t = Tetris::new(EnvConfig::default());
+    let h = t.board.height;
+    let rows = ["...#......", "#...######", "##.#######"];
+    for (i, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == '#' {
+                t.board.datas[h - 3 + i][x] = Unit(Some(Color::White));
+            }
         }
     }
+    t.start();
+    t.now_brick = Some(Brick::new(BrickType::T));
+    t.now_brick_position = (2, (h - 2) as isize);
+    assert!(t.try_rotate());
+    assert_eq!(t.now_brick_position, (2, (h - 2) as isize));
+    t.event_sink();
+    let (clear, _) = t.last_clear.unwrap();
+    assert_eq!(clear.tspin, TSpin::Full);
+    assert_eq!(clear.lines, 1);
+    assert_eq!(t.record.tspins, 1);
+
+    // moving after the rotation cancels the spin
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    t.now_brick = Some(Brick::new(BrickType::T));
+    t.event_rotate();
+    t.event_left();
+    assert_eq!(t.last_kick, None);
+}
+
+#[test]
+fn das_game_test() {
+    let mut t = Tetris::new(EnvConfig {
+        arr: Duration::ZERO,
+        ..Default::default()
+    });
+    t.start();
+    let x = t.now_brick_position.0;
+    t.press(Input::Left);
+    assert_eq!(t.now_brick_position.0, x - 1);
+    // a repeat of the held key does not move again
+    t.press(Input::Left);
+    assert_eq!(t.now_brick_position.0, x - 1);
+    // after DAS, ARR 0 slides to the wall
+    t.tick(Duration::from_millis(200));
+    assert_eq!(t.get_absolute().iter().map(|p| p.0).min(), Some(0));
+    t.release(Input::Left);
+
+    // held soft drop falls SDF times faster
+    let y = t.now_brick_position.1;
+    t.press(Input::SoftDrop);
+    t.tick(Duration::from_millis(200));
+    assert!(t.now_brick_position.1 >= y + 4);
+}
+
+#[test]
+fn pause_test() {
+    let mut t = Tetris::new(EnvConfig::default());
+    // not started yet: nothing to pause or resume
+    t.event_pause();
+    assert!(!t.is_running() && !t.is_paused());
+
+    t.start();
+    t.press(Input::Left);
+    t.event_pause();
+    assert!(t.is_paused());
+    assert!(!t.handling.is_down(Input::Left));
+    // frozen: no gravity, no input
+    let pos = t.now_brick_position;
+    t.tick(Duration::from_millis(200));
+    t.event_right();
+    t.event_sink();
+    assert_eq!(t.now_brick_position, pos);
+    assert_eq!(t.frames, 0);
+
+    t.event_pause();
+    assert!(t.is_running());
+    t.event_right();
+    assert_eq!(t.now_brick_position.0, pos.0 + 1);
+}
+
+#[test]
+fn reset_test() {
+    let mut t = Tetris::new(EnvConfig {
+        mode: crate::mode::Mode::Sprint,
+        ..Default::default()
+    });
+    t.handling.release_timeout = Some(Duration::from_millis(100));
+    t.start();
+    for _ in 0..100 {
+        t.event_sink();
+    }
+    assert!(t.is_over());
+
+    t.cfg.width = 12;
+    t.reset();
+    assert!(!t.is_over() && !t.is_running());
+    assert_eq!(t.board.width, 12);
+    assert_eq!(t.record.score, 0);
+    assert!(t.board.is_empty());
+    assert_eq!(t.handling.release_timeout, Some(Duration::from_millis(100)));
+
+    // 自定义的随机器重来时还用它, 从头发同样的砖块
+    let deal = |t: &Tetris| {
+        t.following_bricks
+            .iter()
+            .map(|b| b.brick_type)
+            .collect::<Vec<_>>()
+    };
+    let custom = crate::randomizer::RandomizerKind::Nes.build(false, Some(5));
+    let mut c = Tetris::with_randomizer(EnvConfig::default(), custom);
+    let first = deal(&c);
+    c.start();
+    c.event_sink();
+    c.reset();
+    assert_eq!(deal(&c), first);
+
+    // sprint ends once the rows are cleared
+    t.start();
+    t.record.eliminate_rows = crate::mode::SPRINT_LINES;
+    t.tick(FRAME);
+    assert_eq!(t.status, GameStatus::Exit("sprint complete".to_string()));
 }
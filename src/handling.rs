@@ -0,0 +1,180 @@
+//! Delayed Auto Shift (DAS), Auto Repeat Rate (ARR) and held soft drop.
+//!
+//! Frontends report key-down with `Tetris::press` and key-up with `Tetris::release`.
+//! Terminals that cannot report releases set `Handling::release_timeout`: a key is
+//! then considered released when no press (or terminal key-repeat) arrived for that long.
+
+use std::time::Duration;
+
+/// Inputs that act while held, everything else is a one-shot event.
+#[derive(strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy)]
+#[strum(serialize_all = "snake_case")]
+pub enum Input {
+    Left,
+    Right,
+    SoftDrop,
+}
+
+impl Input {
+    fn index(self) -> usize {
+        match self {
+            Input::Left => 0,
+            Input::Right => 1,
+            Input::SoftDrop => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Key {
+    down: bool,
+    /// time since the last press or repeat of this key
+    idle: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Handling {
+    /// auto-release keys after this long without a press, for terminals without key-up events
+    pub release_timeout: Option<Duration>,
+    keys: [Key; 3],
+    /// the horizontal direction being auto-shifted, the last one pressed wins
+    shift: Option<Input>,
+    das_timer: Duration,
+    arr_timer: Duration,
+}
+
+/// what the engine should do this frame
+#[derive(Debug, PartialEq, Eq)]
+pub enum Shift {
+    None,
+    /// move this many cells in the direction
+    Cells(Input, usize),
+    /// ARR 0: move as far as possible
+    Wall(Input),
+}
+
+impl Handling {
+    pub fn is_down(&self, input: Input) -> bool {
+        self.keys[input.index()].down
+    }
+
+    /// key-down. returns true for a new press, false for a repeat of a held key.
+    pub fn press(&mut self, input: Input) -> bool {
+        let key = &mut self.keys[input.index()];
+        key.idle = Duration::ZERO;
+        if key.down {
+            return false;
+        }
+        key.down = true;
+        if input != Input::SoftDrop {
+            self.shift = Some(input);
+            self.das_timer = Duration::ZERO;
+            self.arr_timer = Duration::ZERO;
+        }
+        true
+    }
+
+    pub fn release(&mut self, input: Input) {
+        self.keys[input.index()].down = false;
+        if self.shift == Some(input) {
+            // 另一个方向还按着就接着它移动, 重新计算DAS
+            let other = match input {
+                Input::Left => Input::Right,
+                _ => Input::Left,
+            };
+            self.shift = match self.is_down(other) {
+                true => Some(other),
+                false => None,
+            };
+            self.das_timer = Duration::ZERO;
+            self.arr_timer = Duration::ZERO;
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        for input in [Input::Left, Input::Right, Input::SoftDrop] {
+            self.release(input);
+        }
+    }
+
+    /// advance one frame of `frame` length and report the auto-shift to apply
+    pub fn frame(&mut self, frame: Duration, das: Duration, arr: Duration) -> Shift {
+        if let Some(timeout) = self.release_timeout {
+            for input in [Input::Left, Input::Right, Input::SoftDrop] {
+                let key = &mut self.keys[input.index()];
+                key.idle += frame;
+                if key.down && key.idle > timeout {
+                    self.release(input);
+                }
+            }
+        }
+        let dir = match self.shift {
+            Some(d) => d,
+            None => return Shift::None,
+        };
+        if self.das_timer < das {
+            self.das_timer += frame;
+            if self.das_timer < das {
+                return Shift::None;
+            }
+            // the frame DAS charges in carries over into ARR
+            self.arr_timer = self.das_timer - das;
+        } else {
+            self.arr_timer += frame;
+        }
+        if arr.is_zero() {
+            return Shift::Wall(dir);
+        }
+        let mut cells = 0;
+        while self.arr_timer >= arr {
+            self.arr_timer -= arr;
+            cells += 1;
+        }
+        match cells {
+            0 => Shift::None,
+            n => Shift::Cells(dir, n),
+        }
+    }
+}
+
+#[test]
+fn das_test() {
+    let frame = Duration::from_millis(10);
+    let das = Duration::from_millis(50);
+    let arr = Duration::from_millis(20);
+    let mut h = Handling::default();
+    assert!(h.press(Input::Left));
+    assert!(!h.press(Input::Left));
+    let shifts: Vec<Shift> = (0..8).map(|_| h.frame(frame, das, arr)).collect();
+    assert_eq!(shifts[3], Shift::None);
+    assert_eq!(shifts[4], Shift::None);
+    assert_eq!(shifts[6], Shift::Cells(Input::Left, 1));
+
+    // right takes over, left resumes once right is released
+    h.press(Input::Right);
+    assert_eq!(h.frame(frame, das, arr), Shift::None);
+    h.release(Input::Right);
+    assert_eq!(h.shift, Some(Input::Left));
+
+    // ARR 0 slides to the wall
+    for _ in 0..5 {
+        h.frame(frame, das, Duration::ZERO);
+    }
+    assert_eq!(
+        h.frame(frame, das, Duration::ZERO),
+        Shift::Wall(Input::Left)
+    );
+
+    // terminals without key-up: released after the timeout
+    let mut h = Handling {
+        release_timeout: Some(Duration::from_millis(30)),
+        ..Default::default()
+    };
+    h.press(Input::SoftDrop);
+    for _ in 0..3 {
+        h.frame(frame, das, arr);
+    }
+    assert!(h.is_down(Input::SoftDrop));
+    h.frame(frame, das, arr);
+    assert!(!h.is_down(Input::SoftDrop));
+}
@@ -0,0 +1,185 @@
+use std::sync::mpsc::Sender;
+
+use crossterm::{
+    event::{
+        self, DisableFocusChange, EnableFocusChange, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
+    execute, terminal,
+};
+use tetris_rs::keys::{Control, Key, KeyBindings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Quit,
+
+    Rotate,
+
+    RotateCcw,
+
+    Rotate180,
+
+    Left,
+
+    Right,
+
+    Accelerate,
+
+    Sink,
+
+    Hold,
+
+    Pause,
+
+    /// enter, picks a menu item
+    Confirm,
+
+    /// the terminal window lost focus
+    FocusLost,
+
+    /// the terminal was resized
+    Resize,
+}
+
+/// what the input thread reports: keys are turned into signals by the game loop,
+/// which knows whether they are controls or text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermEvent {
+    Key(Key),
+    FocusLost,
+    Resize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Press,
+    /// key held down, only reported by terminals with the kitty keyboard protocol
+    Repeat,
+    Release,
+}
+
+/// Turn on the kitty keyboard protocol if the terminal supports it.
+/// returns true when key releases will be reported.
+pub fn enable_key_release() -> bool {
+    if !terminal::supports_keyboard_enhancement().unwrap_or(false) {
+        return false;
+    }
+    execute!(
+        std::io::stdout(),
+        PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+        )
+    )
+    .is_ok()
+}
+
+pub fn disable_key_release() {
+    execute!(std::io::stdout(), PopKeyboardEnhancementFlags).ok();
+}
+
+/// report focus changes, used to pause the game when the terminal loses focus
+pub fn enable_focus_change() {
+    execute!(std::io::stdout(), EnableFocusChange).ok();
+}
+
+pub fn disable_focus_change() {
+    execute!(std::io::stdout(), DisableFocusChange).ok();
+}
+
+/// the key of a crossterm event, None for keys that can't be bound
+fn key_of(key: &KeyEvent) -> Option<Key> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char(c) => Some(Key::Ctrl(c.to_ascii_lowercase())),
+            _ => None,
+        };
+    }
+    match key.code {
+        KeyCode::Char(' ') => Some(Key::Space),
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Esc => Some(Key::Esc),
+        KeyCode::Tab => Some(Key::Tab),
+        KeyCode::Backspace => Some(Key::Backspace),
+        _ => None,
+    }
+}
+
+/// the signal of an event, None for keys that aren't bound
+pub fn signal_of(event: TermEvent, keys: &KeyBindings) -> Option<Signal> {
+    let key = match event {
+        TermEvent::Key(key) => key,
+        TermEvent::FocusLost => return Some(Signal::FocusLost),
+        TermEvent::Resize => return Some(Signal::Resize),
+    };
+    let control = match keys.control(key) {
+        Some(control) => control,
+        // 没有绑定到别处的 ctrl-c 总能退出, 免得解绑了退出键后出不去
+        None if key == Key::Ctrl('c') => Control::Quit,
+        None => return None,
+    };
+    Some(match control {
+        Control::Left => Signal::Left,
+        Control::Right => Signal::Right,
+        Control::SoftDrop => Signal::Accelerate,
+        Control::HardDrop => Signal::Sink,
+        Control::Rotate => Signal::Rotate,
+        Control::RotateCcw => Signal::RotateCcw,
+        Control::Rotate180 => Signal::Rotate180,
+        Control::Hold => Signal::Hold,
+        Control::Pause => Signal::Pause,
+        Control::Quit => Signal::Quit,
+        Control::Confirm => Signal::Confirm,
+    })
+}
+
+// 监听键盘事件, 通过管道发送给游戏主线程
+pub fn listen_key_event(tx: Sender<(TermEvent, KeyAction)>) {
+    loop {
+        let (event, action) = match event::read() {
+            Ok(Event::Key(key)) => match key_of(&key) {
+                Some(k) => (
+                    TermEvent::Key(k),
+                    match key.kind {
+                        KeyEventKind::Press => KeyAction::Press,
+                        KeyEventKind::Repeat => KeyAction::Repeat,
+                        KeyEventKind::Release => KeyAction::Release,
+                    },
+                ),
+                None => continue,
+            },
+            Ok(Event::FocusLost) => (TermEvent::FocusLost, KeyAction::Press),
+            Ok(Event::Resize(..)) => (TermEvent::Resize, KeyAction::Press),
+            Ok(_) => continue,
+            Err(_) => return,
+        };
+        if tx.send((event, action)).is_err() {
+            return;
+        }
+    }
+}
+
+#[test]
+fn signal_test() {
+    let keys = KeyBindings::preset(tetris_rs::keys::KeysPreset::Vim);
+    let event = KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE);
+    assert_eq!(
+        key_of(&event).and_then(|k| signal_of(TermEvent::Key(k), &keys)),
+        Some(Signal::Left)
+    );
+    assert_eq!(signal_of(TermEvent::Key(Key::Char('y')), &keys), None);
+
+    // ctrl-c quits even when unbound
+    let mut keys = KeyBindings::default();
+    keys.set(Control::Quit, vec![Key::Char('q')]);
+    assert_eq!(
+        signal_of(TermEvent::Key(Key::Ctrl('c')), &keys),
+        Some(Signal::Quit)
+    );
+}
@@ -0,0 +1,291 @@
+//! Key bindings: which keys trigger which control.
+//!
+//! Keys are named like `left`, `space`, `esc`, `ctrl-c` or a single character like `x`.
+//! Every control can have several keys, a key can only be bound to one control.
+
+use std::{fmt, str::FromStr};
+
+use crate::env::ConfigError;
+
+/// something a key can be bound to
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Hash,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum Control {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    Rotate,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    /// pause or resume, goes back in menus
+    Pause,
+    Quit,
+    /// picks a menu item
+    Confirm,
+}
+
+impl Control {
+    pub const ALL: [Control; 11] = [
+        Control::Left,
+        Control::Right,
+        Control::SoftDrop,
+        Control::HardDrop,
+        Control::Rotate,
+        Control::RotateCcw,
+        Control::Rotate180,
+        Control::Hold,
+        Control::Pause,
+        Control::Quit,
+        Control::Confirm,
+    ];
+}
+
+/// a key on the keyboard, without the terminal library in between
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Key {
+    Char(char),
+    /// a character with ctrl held
+    Ctrl(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Space,
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Ctrl(c) => write!(f, "ctrl-{}", c),
+            Key::Up => write!(f, "up"),
+            Key::Down => write!(f, "down"),
+            Key::Left => write!(f, "left"),
+            Key::Right => write!(f, "right"),
+            Key::Space => write!(f, "space"),
+            Key::Enter => write!(f, "enter"),
+            Key::Esc => write!(f, "esc"),
+            Key::Tab => write!(f, "tab"),
+            Key::Backspace => write!(f, "backspace"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let one = |s: &str| {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_whitespace() && !c.is_control() => Some(c),
+                _ => None,
+            }
+        };
+        match s {
+            "up" => Ok(Key::Up),
+            "down" => Ok(Key::Down),
+            "left" => Ok(Key::Left),
+            "right" => Ok(Key::Right),
+            "space" | " " => Ok(Key::Space),
+            "enter" => Ok(Key::Enter),
+            "esc" => Ok(Key::Esc),
+            "tab" => Ok(Key::Tab),
+            "backspace" => Ok(Key::Backspace),
+            _ => match s.strip_prefix("ctrl-") {
+                Some(c) => one(c).map(|c| Key::Ctrl(c.to_ascii_lowercase())),
+                None => one(s).map(Key::Char),
+            }
+            .ok_or_else(|| format!("unknown key {}", s)),
+        }
+    }
+}
+
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum KeysPreset {
+    /// arrows, space to drop, x/z/a to rotate
+    #[default]
+    Default,
+    /// h/j/k/l next to the arrows
+    Vim,
+    /// w/a/s/d next to the arrows, q/e to rotate
+    Wasd,
+}
+
+impl KeysPreset {
+    pub const ALL: [KeysPreset; 3] = [KeysPreset::Default, KeysPreset::Vim, KeysPreset::Wasd];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    /// the keys of every control, in the order of `Control::ALL`
+    keys: Vec<Vec<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::preset(KeysPreset::default())
+    }
+}
+
+impl KeyBindings {
+    pub fn preset(preset: KeysPreset) -> Self {
+        use Key::*;
+        let (left, right, soft_drop, rotate, rotate_ccw, rotate_180, quit) = match preset {
+            KeysPreset::Default => (
+                vec![Left],
+                vec![Right],
+                vec![Down],
+                vec![Up, Char('x')],
+                vec![Char('z')],
+                vec![Char('a')],
+                vec![Char('q'), Ctrl('c')],
+            ),
+            KeysPreset::Vim => (
+                vec![Char('h'), Left],
+                vec![Char('l'), Right],
+                vec![Char('j'), Down],
+                vec![Char('k'), Up, Char('x')],
+                vec![Char('z')],
+                vec![Char('a')],
+                vec![Char('q'), Ctrl('c')],
+            ),
+            KeysPreset::Wasd => (
+                vec![Char('a'), Left],
+                vec![Char('d'), Right],
+                vec![Char('s'), Down],
+                vec![Char('w'), Up, Char('e')],
+                vec![Char('q')],
+                vec![Char('r')],
+                vec![Ctrl('c')],
+            ),
+        };
+        Self {
+            keys: vec![
+                left,
+                right,
+                soft_drop,
+                vec![Space],
+                rotate,
+                rotate_ccw,
+                rotate_180,
+                vec![Char('c')],
+                vec![Char('p'), Esc],
+                quit,
+                vec![Enter],
+            ],
+        }
+    }
+
+    fn index(control: Control) -> usize {
+        Control::ALL.iter().position(|&c| c == control).unwrap()
+    }
+
+    pub fn keys(&self, control: Control) -> &[Key] {
+        &self.keys[Self::index(control)]
+    }
+
+    /// replace the keys of `control`
+    pub fn set(&mut self, control: Control, keys: Vec<Key>) {
+        self.keys[Self::index(control)] = keys;
+    }
+
+    /// the control `key` is bound to
+    pub fn control(&self, key: Key) -> Option<Control> {
+        Control::ALL
+            .into_iter()
+            .find(|&c| self.keys(c).contains(&key))
+    }
+
+    /// an error naming the first key bound to two controls
+    pub fn check(&self) -> Result<(), ConfigError> {
+        for (i, first) in Control::ALL.into_iter().enumerate() {
+            for second in Control::ALL.into_iter().skip(i + 1) {
+                if let Some(&key) = self
+                    .keys(first)
+                    .iter()
+                    .find(|k| self.keys(second).contains(k))
+                {
+                    return Err(ConfigError::KeyConflict {
+                        key,
+                        first,
+                        second,
+                        source: None,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the keys of `control` for help texts, like `up/x`
+    pub fn names(&self, control: Control) -> String {
+        match self.keys(control) {
+            [] => "(unbound)".to_string(),
+            keys => keys
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+                .join("/"),
+        }
+    }
+
+    /// the help line shown under the board
+    pub fn help(&self) -> String {
+        let n = |c| self.names(c);
+        format!(
+            "{} {} move, {} soft drop, {} drop, {} hold.\n{} rotate, {} counter-clockwise, {} 180, {} pause, {} quit.",
+            n(Control::Left),
+            n(Control::Right),
+            n(Control::SoftDrop),
+            n(Control::HardDrop),
+            n(Control::Hold),
+            n(Control::Rotate),
+            n(Control::RotateCcw),
+            n(Control::Rotate180),
+            n(Control::Pause),
+            n(Control::Quit),
+        )
+    }
+}
+
+/// keys separated by commas or spaces, like `h, left`
+pub fn parse_keys(s: &str) -> Result<Vec<Key>, String> {
+    s.split([',', ' '])
+        .filter(|k| !k.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[test]
+fn keys_test() {
+    for preset in KeysPreset::ALL {
+        assert_eq!(KeyBindings::preset(preset).check(), Ok(()));
+    }
+    let mut keys = KeyBindings::default();
+    assert_eq!(keys.control(Key::Char('x')), Some(Control::Rotate));
+    assert_eq!(keys.control(Key::Char('h')), None);
+    assert_eq!(
+        parse_keys("h, left ctrl-C"),
+        Ok(vec![Key::Char('h'), Key::Left, Key::Ctrl('c')])
+    );
+    assert!(parse_keys("f13").is_err());
+
+    keys.set(Control::Hold, vec![Key::Char('x')]);
+    assert_eq!(
+        keys.check().unwrap_err().to_string(),
+        "key x is bound to both rotate and hold"
+    );
+    assert!(KeyBindings::default().help().starts_with("left right move"));
+}
@@ -0,0 +1,43 @@
+//! tetris-rs game engine.
+//!
+//! The engine (board, bricks, rules and scoring) lives here, so it can be driven by bots,
+//! analysis tools or any frontend. Without the default `terminal` feature it doesn't pull in
+//! termion, crossterm, clap or dotenv; brick and theme colors are still `colored::Color` values.
+//! The `tetris` binary is a thin terminal frontend built on top of it.
+//!
+//! ```
+//! use tetris_rs::{env::EnvConfig, game::Tetris};
+//!
+//! let mut t = Tetris::new(EnvConfig::default());
+//! t.start();
+//! t.event_sink();
+//! assert!(t.record.score > 0);
+//! ```
+
+pub mod bricks;
+pub mod config;
+pub mod env;
+pub mod game;
+pub mod handling;
+pub mod keys;
+pub mod lock;
+pub mod mode;
+pub mod randomizer;
+pub mod record;
+pub mod render;
+pub mod replay;
+pub mod rotation;
+pub mod scores;
+pub mod scoring;
+pub mod spin;
+pub mod texture;
+pub mod theme;
+
+pub use bricks::{Brick, BrickType};
+pub use env::EnvConfig;
+pub use game::{Board, GameStatus, Tetris};
+pub use mode::Mode;
+pub use randomizer::{Randomizer, RandomizerKind};
+pub use record::Record;
+pub use rotation::Rotation;
+pub use scoring::{Scoring, ScoringKind};
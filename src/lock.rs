@@ -0,0 +1,126 @@
+//! Lock delay: how long a grounded brick may still slide or spin before it locks.
+
+use std::time::Duration;
+
+/// What resets the lock timer of a grounded brick.
+#[derive(strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy)]
+#[strum(serialize_all = "lowercase")]
+pub enum LockReset {
+    /// guideline "extended placement": every successful move or rotation after landing
+    /// resets the timer, at most `lock_reset_limit` times per row the brick reaches.
+    Move,
+    /// classic step reset: only falling to a new lowest row resets the timer.
+    Step,
+    /// the timer never resets, a brick gets one lock delay in total.
+    None,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LockDelay {
+    /// time spent on the ground since the last reset
+    pub timer: Duration,
+    /// move/rotate resets used since the brick reached `lowest_row`
+    pub resets: usize,
+    /// lowest row (largest y) the brick origin has reached
+    pub lowest_row: isize,
+    /// the brick touched the ground since reaching `lowest_row`, only then moves use up resets
+    pub landed: bool,
+}
+
+impl LockDelay {
+    pub fn new(row: isize) -> Self {
+        Self {
+            timer: Duration::ZERO,
+            resets: 0,
+            lowest_row: row,
+            landed: false,
+        }
+    }
+
+    /// a successful move or rotation, `grounded` when the brick is on the ground after it.
+    /// moves in the air don't count. returns true if it reset the timer.
+    pub fn on_action(&mut self, mode: LockReset, limit: usize, grounded: bool) -> bool {
+        self.landed |= grounded;
+        if mode != LockReset::Move || !self.landed || self.resets >= limit {
+            return false;
+        }
+        self.resets += 1;
+        self.timer = Duration::ZERO;
+        true
+    }
+
+    /// the brick moved down to `row`
+    pub fn on_fall(&mut self, mode: LockReset, row: isize) {
+        if row <= self.lowest_row {
+            return;
+        }
+        self.lowest_row = row;
+        self.landed = false;
+        match mode {
+            LockReset::Move => {
+                self.resets = 0;
+                self.timer = Duration::ZERO;
+            }
+            LockReset::Step => self.timer = Duration::ZERO,
+            LockReset::None => {}
+        }
+    }
+
+    /// advance the timer by `elapsed` while grounded. returns true when the brick must lock.
+    pub fn tick(
+        &mut self,
+        elapsed: Duration,
+        delay: Duration,
+        mode: LockReset,
+        limit: usize,
+    ) -> bool {
+        self.landed = true;
+        self.timer += elapsed;
+        // 用完重置次数后一落地就锁定
+        self.timer >= delay || (mode == LockReset::Move && self.resets >= limit)
+    }
+}
+
+#[test]
+fn lock_delay_test() {
+    let delay = Duration::from_millis(500);
+    let step = Duration::from_millis(100);
+    let mut l = LockDelay::new(0);
+    for _ in 0..4 {
+        assert!(!l.tick(step, delay, LockReset::Move, 15));
+    }
+    // a move resets the timer
+    assert!(l.on_action(LockReset::Move, 15, true));
+    assert!(!l.tick(step * 4, delay, LockReset::Move, 15));
+    assert!(l.tick(step, delay, LockReset::Move, 15));
+
+    // but not more than the limit
+    let mut l = LockDelay::new(0);
+    for _ in 0..15 {
+        assert!(l.on_action(LockReset::Move, 15, true));
+    }
+    assert!(!l.on_action(LockReset::Move, 15, true));
+    assert!(l.tick(Duration::ZERO, delay, LockReset::Move, 15));
+    // falling to a new row gives the resets back
+    l.on_fall(LockReset::Move, 1);
+    assert!(l.on_action(LockReset::Move, 15, true));
+
+    // step reset ignores moves
+    let mut l = LockDelay::new(0);
+    assert!(!l.on_action(LockReset::Step, 15, true));
+    l.tick(step * 4, delay, LockReset::Step, 15);
+    l.on_fall(LockReset::Step, 1);
+    assert_eq!(l.timer, Duration::ZERO);
+
+    // moves in the air don't use up resets, the ones after landing do
+    let mut l = LockDelay::new(0);
+    for _ in 0..20 {
+        assert!(!l.on_action(LockReset::Move, 15, false));
+    }
+    assert_eq!(l.resets, 0);
+    assert!(l.on_action(LockReset::Move, 15, true));
+    // sliding off a ledge still counts once landed
+    assert!(l.on_action(LockReset::Move, 15, false));
+    l.on_fall(LockReset::Move, 1);
+    assert!(!l.on_action(LockReset::Move, 15, false));
+}
@@ -1,124 +1,334 @@
 use std::{
-    io::{stdin, stdout, Write},
+    io,
+    path::PathBuf,
     process,
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::Receiver,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use clap::Parser;
+use cli::{Cli, Command};
 use colored::{Color, Colorize};
-use crossterm::terminal::disable_raw_mode;
-use display::TerminalPainter;
-use game::{GameStatus, Tetris};
-use termion::{input::TermRead, raw::IntoRawMode};
-
-pub mod bricks;
-pub mod display;
-pub mod env;
-pub mod game;
-pub mod record;
-
-#[derive(Debug)]
-pub enum Signal {
-    Quit,
-
-    Rotate,
-
-    Left,
-
-    Right,
+use input::{KeyAction, Signal, TermEvent};
+use menu::{Menu, Screen};
+use tetris_rs::{
+    env::{self, EnvConfig},
+    game::Tetris,
+    handling::Input,
+    record::Record,
+    render::{center, paint_fit, Fit, PlainText, Renderer, TermionRenderer, View},
+    replay::{Action, Player, Replay},
+    scores::{self, Score},
+    theme::ColorDepth,
+};
 
-    Accelerate,
+mod cli;
+mod input;
+mod menu;
 
-    Sink,
+/// columns and rows of the terminal
+fn terminal_size() -> (usize, usize) {
+    let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+    (cols as usize, rows as usize)
 }
 
-fn clear_screen() {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    write!(
-        stdout,
-        "{}{}{}",
-        termion::scroll::Up(10),
-        termion::cursor::Goto(1, 1),
-        termion::clear::All
-    )
-    .unwrap();
+/// the game action of a key, None for keys the game ignores
+fn action_of(signal: Signal, action: KeyAction) -> Option<Action> {
+    // 左右和软降按住生效, 交给引擎的DAS处理. 其他按键只响应按下
+    let held = match signal {
+        Signal::Left => Some(Input::Left),
+        Signal::Right => Some(Input::Right),
+        Signal::Accelerate => Some(Input::SoftDrop),
+        _ => None,
+    };
+    match (held, action) {
+        (Some(input), KeyAction::Release) => Some(Action::Release(input)),
+        (Some(input), _) => Some(Action::Press(input)),
+        (None, KeyAction::Press) => match signal {
+            Signal::Quit => Some(Action::Quit),
+            Signal::Rotate => Some(Action::Rotate),
+            Signal::RotateCcw => Some(Action::RotateCcw),
+            Signal::Rotate180 => Some(Action::Rotate180),
+            Signal::Sink => Some(Action::Sink),
+            Signal::Hold => Some(Action::Hold),
+            Signal::Pause => Some(Action::TogglePause),
+            Signal::FocusLost => Some(Action::Pause),
+            Signal::Confirm | Signal::Resize => None,
+            Signal::Left | Signal::Right | Signal::Accelerate => None,
+        },
+        (None, _) => None,
+    }
 }
 
-fn listen_key_event(tx: Sender<Option<Signal>>) {
-    let stdin = stdin();
-    for c in stdin.keys() {
-        match c.unwrap() {
-            termion::event::Key::Up => tx.send(Some(Signal::Rotate)).unwrap(),
-            termion::event::Key::Down => tx.send(Some(Signal::Accelerate)).unwrap(),
-            termion::event::Key::Left => tx.send(Some(Signal::Left)).unwrap(),
-            termion::event::Key::Right => tx.send(Some(Signal::Right)).unwrap(),
-            termion::event::Key::Char(' ') => tx.send(Some(Signal::Sink)).unwrap(),
-            termion::event::Key::Ctrl('c') | termion::event::Key::Char('q') => {
-                tx.send(Some(Signal::Quit)).unwrap()
-            }
-            _ => tx.send(None).unwrap(),
-        };
-    }
+/// what a session does besides the menu
+#[derive(Default)]
+struct Session {
+    /// watch this replay first
+    player: Option<Player>,
+    /// start a game right away
+    start: bool,
+    /// save the replay of the last game here
+    record: Option<PathBuf>,
 }
 
-fn launch(mut t: Tetris, rx: Receiver<Option<Signal>>) {
-    let mut stdout = stdout().into_raw_mode().unwrap();
+fn launch<R: Renderer<Output = io::Result<()>>>(
+    mut t: Tetris,
+    rx: Receiver<(TermEvent, KeyAction)>,
+    mut renderer: R,
+    mut session: Session,
+) -> Option<Record> {
+    let mut menu = Menu::new();
+    let mut last = Instant::now();
+    let mut dirty = true;
+    let mut size = terminal_size();
+    // 正在录制的对局, 看回放时为空
+    let mut replay = None;
+    // 退出终端界面后再显示的错误
+    let mut errors = vec![];
 
-    write!(stdout, "{}", termion::cursor::Hide).unwrap();
-    //
-    let mut counter = 0;
+    // 以前的成绩, 用来判断新纪录和显示排行榜
+    if let Some(path) = scores::path() {
+        match scores::load(&path) {
+            Ok(saved) => menu.saved = saved,
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    if session.player.is_some() {
+        menu.screen = Screen::Playing;
+    } else if session.start {
+        menu.play(&mut t);
+        replay = Some(Replay::new(&t));
+    }
 
     loop {
-        clear_screen();
-        t.update_by(counter);
-
-        // 接收管道内容
-        match rx.try_recv() {
-            Ok(Some(signal)) => match signal {
-                Signal::Quit => t.event_quit(),
-                Signal::Rotate => t.event_rotate(),
-                Signal::Left => t.event_left(),
-                Signal::Right => t.event_right(),
-                Signal::Accelerate => t.event_accelerate(),
-                Signal::Sink => t.event_sink(),
-            },
-            Ok(None) | Err(_) => {}
+        // 接收管道内容, 菜单只响应按下
+        let mut quit = false;
+        while let Ok((event, action)) = rx.try_recv() {
+            dirty = true;
+            // 输入名字时按键就是文字
+            if let (Screen::Name, TermEvent::Key(key), KeyAction::Press) =
+                (menu.screen, event, action)
+            {
+                if menu.type_key(key) {
+                    continue;
+                }
+            }
+            let signal = match input::signal_of(event, &t.cfg.keys) {
+                Some(signal) => signal,
+                None => continue,
+            };
+            if signal == Signal::Resize {
+                // 尺寸变了, 屏幕上的内容不可信, 整屏重绘
+                size = terminal_size();
+                renderer.invalidate();
+            }
+            match menu.screen {
+                // 看回放时只能退出
+                Screen::Playing if session.player.is_some() && signal == Signal::Quit => {
+                    t.event_quit()
+                }
+                Screen::Playing if session.player.is_some() => {}
+                Screen::Playing => match (action_of(signal, action), replay.as_mut()) {
+                    (Some(a), Some(r)) => r.record(&mut t, a),
+                    (Some(a), None) => a.apply(&mut t),
+                    (None, _) => {}
+                },
+                _ if action == KeyAction::Press => {
+                    quit |= menu.handle(&mut t, signal);
+                    // 新的一局开始了, 开始录制
+                    if menu.screen == Screen::Playing {
+                        replay = Some(Replay::new(&t));
+                    }
+                }
+                _ => {}
+            }
+        }
+        // 退出时输入到一半的名字也保存
+        if quit && menu.screen == Screen::Name {
+            menu.finish_name();
+        }
+        errors.extend(save_scores(menu.take_unsaved()));
+        if quit {
+            break;
         }
 
-        TerminalPainter::draw_game(&t);
+        // 按真实经过的时间推进游戏, 与绘制频率无关
+        let now = Instant::now();
+        if menu.screen == Screen::Playing {
+            dirty |= match session.player.as_mut() {
+                Some(player) => player.tick(&mut t, now - last) > 0,
+                None => t.tick(now - last) > 0,
+            };
+            let done = match &session.player {
+                Some(player) => player.is_done(&t),
+                None => t.is_over(),
+            };
+            if done {
+                menu.game_over(&t);
+                // 回放看完后不保存成绩
+                if session.player.take().is_none() {
+                    menu.add_score(Score::new(&t, &scores::default_name()));
+                    if let (Some(replay), Some(path)) = (replay.take(), &session.record) {
+                        if let Err(e) = replay.save(path) {
+                            errors.push(format!("{}: {}", path.display(), e));
+                        }
+                    }
+                }
+            }
+        }
+        last = now;
 
-        if let GameStatus::Exit(ref e) = t.status {
-            TerminalPainter::draw_record(&t);
-            write!(stdout, "{}", termion::cursor::Show).unwrap();
-            write!(stdout, "{}", crossterm::cursor::MoveToColumn(0)).unwrap();
-            TerminalPainter::raw_write_fix(format!("{} {}", "[exit]".color(Color::Blue), e));
-            disable_raw_mode().unwrap();
-            process::exit(0);
+        // 只有状态变化时才重绘, 并且只输出变化的格子
+        if dirty {
+            let (cols, rows) = size;
+            let frame = match menu.screen {
+                Screen::Playing => {
+                    let (frame, fit) = paint_fit(&View::new(&t), cols, rows);
+                    // 放不下棋盘时先暂停, 等窗口变大
+                    // 暂停也要录下来, 回放时只照着录下的输入走
+                    if matches!(fit, Fit::TooSmall(..))
+                        && t.is_running()
+                        && session.player.is_none()
+                    {
+                        match replay.as_mut() {
+                            Some(r) => r.record(&mut t, Action::Pause),
+                            None => t.pause(),
+                        }
+                    }
+                    frame
+                }
+                _ => {
+                    let frame = menu.view(&t).paint();
+                    center(&frame, frame.size(), cols, rows)
+                }
+            };
+            renderer.draw(&frame).unwrap();
+            dirty = false;
         }
-        counter += 1;
         thread::sleep(Duration::from_millis(10));
     }
+
+    // 键盘协议的设置是跟着屏幕走的, 要在离开备用屏幕之前恢复
+    input::disable_key_release();
+    input::disable_focus_change();
+    drop(renderer);
+    for e in errors {
+        println!("{} {}", "[save error]".color(Color::Red), e);
+    }
+    match menu.scores.is_empty() {
+        true => None,
+        false => Some(t.record),
+    }
+}
+
+/// append finished games to the scores file, returns what went wrong
+fn save_scores(games: Vec<Score>) -> Vec<String> {
+    if games.is_empty() {
+        return vec![];
+    }
+    let path = match scores::path() {
+        Some(path) => path,
+        None => return vec!["no data directory for scores, set XDG_DATA_HOME".to_string()],
+    };
+    games
+        .iter()
+        .filter_map(|score| scores::append(&path, score).err())
+        .map(|e| format!("{}: {}", path.display(), e))
+        .collect()
+}
+
+/// `--headless`: play without a terminal at full speed and print how it ended
+fn headless(cfg: EnvConfig, replay: Option<Replay>) {
+    let replay = replay.unwrap_or_else(|| Replay::new(&Tetris::new(cfg)));
+    let (mut player, mut t) = Player::new(replay);
+    player.run(&mut t);
+    println!("{}", PlainText.render(&View::new(&t)));
+    println!("{}", t.record);
+    println!("frames: {}, time: {}", t.frames, menu::clock(t.frames));
+}
+
+fn exit_with(kind: &str, e: impl std::fmt::Display) -> ! {
+    println!("{} {}", format!("[{}]", kind).color(Color::Red), e);
+    process::exit(1);
 }
 
 fn main() {
+    let cli = Cli::parse();
+    let mut cfg =
+        env::load_file(cli.play.config.as_deref()).unwrap_or_else(|e| exit_with("config error", e));
+    cli.play.apply(&mut cfg);
+
+    let result = match cli.command {
+        Some(Command::Config { print, init }) => {
+            Some(cli::config_command(print, init, &cli.play, &cfg))
+        }
+        Some(Command::Scores { mode }) => Some(cli::scores_command(mode)),
+        Some(Command::Stats) => Some(cli::stats_command()),
+        None => None,
+    };
+    if let Some(result) = result {
+        if let Err(e) = result {
+            exit_with("error", e);
+        }
+        return;
+    }
+
+    // 开局前才检查, `config --print` 正好用来找出不对的值
+    // 所有来源合并后再检查: 配置文件里的 width = 2 可以被 --width 10 盖掉
+    if let Err(e) = cfg.validate() {
+        exit_with("config error", e);
+    }
+    let replay = cli.play.replay.as_ref().map(|path| {
+        Replay::load(path)
+            .unwrap_or_else(|e| exit_with("replay error", format!("{}: {}", path.display(), e)))
+    });
+    if cli.play.headless {
+        headless(cfg, replay);
+        return;
+    }
+
     // 两个线程 A监听键盘事件 B游戏主线程 数据流向: A ===管道===> B
     let (tx, rx) = std::sync::mpsc::channel();
-    let _cfg = match env::load() {
-        Ok(v) => v,
-        Err(e) => {
-            println!("{} {}", "[config error]".color(Color::Red), e);
-            process::exit(1);
+
+    let mut session = Session {
+        start: cli.play.mode.is_some(),
+        record: cli.play.record.clone(),
+        ..Default::default()
+    };
+    let mut t = match replay {
+        Some(replay) => {
+            let (player, mut t) = Player::new(replay);
+            session.player = Some(player);
+            // 按键用自己的设置, 不用回放里录下的
+            t.cfg.keys = cfg.keys;
+            t
         }
+        // 先进入主菜单, 选择开始后才开局
+        None => Tetris::new(cfg),
     };
+    let depth = t.cfg.color.unwrap_or_else(ColorDepth::detect);
+    if depth == ColorDepth::None {
+        colored::control::set_override(false);
+    }
+    let renderer = TermionRenderer::stdout().unwrap().with_depth(depth);
 
-    let mut t = Tetris::new(_cfg);
+    // kitty 的键盘协议每个屏幕各有一套设置, 进入备用屏幕之后再打开
+    // 回放里记着录制时的设置, 不能改
+    if !input::enable_key_release() && session.player.is_none() {
+        // 终端不支持松开事件: 没有新的按键(或终端的自动重复)就当作松开
+        t.handling.release_timeout = Some(Duration::from_millis(100));
+    }
 
-    t.start();
+    input::enable_focus_change();
 
     // 发送者线程A
-    thread::spawn(move || listen_key_event(tx));
+    thread::spawn(move || input::listen_key_event(tx));
 
     // 接受者线程B
-    launch(t, rx)
+    if let Some(record) = launch(t, rx, renderer, session) {
+        println!("{}", record);
+    }
+    println!("{} bye", "[exit]".color(Color::Blue));
 }
@@ -0,0 +1,492 @@
+// 主菜单, 设置, 成绩和结算画面
+
+use colored::Color;
+use tetris_rs::{
+    game::{GameStatus, Tetris, FRAMES_PER_SECOND},
+    keys::{Control, Key},
+    mode::Mode,
+    randomizer::RandomizerKind,
+    record::Record,
+    render::PaintBoard,
+    scores::{self, Category, Score, TABLE_SIZE},
+    scoring::ScoringKind,
+    texture::{Texture, TexturePreset},
+    theme::{Theme, ThemePreset},
+};
+
+use crate::input::Signal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Main,
+    Settings,
+    Scores,
+    Playing,
+    /// a new record, typing the name to save it under
+    Name,
+    GameOver,
+}
+
+/// what a menu screen shows
+pub struct MenuView {
+    pub title: String,
+    pub text: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+    /// how to drive the menu
+    pub hint: String,
+}
+
+impl MenuView {
+    pub fn paint(&self) -> PaintBoard {
+        let mut painter = PaintBoard::new(40, 1, ' ');
+        painter.paint_string(2, 1, &self.title, Some(Color::BrightYellow));
+        let mut y = 3;
+        for (i, item) in self.items.iter().enumerate() {
+            match i == self.selected {
+                true => {
+                    painter.paint_string(2, y, &format!("> {}", item), Some(Color::BrightGreen))
+                }
+                false => painter.paint_string(4, y, item, None),
+            }
+            y += 1;
+        }
+        painter.paint_string(2, y + 1, &self.text, Some(Color::BrightBlack));
+        painter.paint_string(
+            2,
+            y + 2 + self.text.lines().count(),
+            &self.hint,
+            Some(Color::BrightRed),
+        );
+        painter
+    }
+}
+
+#[derive(Debug)]
+pub struct Menu {
+    pub screen: Screen,
+    pub selected: usize,
+    /// results of this session
+    pub scores: Vec<(Mode, Record, u64)>,
+    /// every saved game, for the high score tables
+    pub saved: Vec<Score>,
+    /// games kept but not written to the scores file yet, see `take_unsaved`
+    unsaved: Vec<Score>,
+    /// a record waiting for its name, and its place
+    pending: Option<(Score, usize)>,
+    /// the name being typed, kept for the next record
+    name: String,
+    /// the place the last game took in its table
+    place: Option<usize>,
+    /// the table shown on the scores screen
+    category: Option<Category>,
+    /// how the last game ended
+    reason: String,
+}
+
+const MAIN_ITEMS: usize = 5;
+const SETTINGS_ITEMS: usize = 11;
+const GAME_OVER_ITEMS: usize = 3;
+/// longest name of a record
+const NAME_LEN: usize = 12;
+
+/// step through `all` from `now`, wrapping around
+fn cycle<T: Copy + PartialEq>(all: &[T], now: T, step: isize) -> T {
+    let i = all.iter().position(|&e| e == now).unwrap_or(0) as isize;
+    all[(i + step).rem_euclid(all.len() as isize) as usize]
+}
+
+fn on_off(b: bool) -> &'static str {
+    match b {
+        true => "on",
+        false => "off",
+    }
+}
+
+/// the preset a texture comes from
+fn texture_name(texture: &Texture) -> String {
+    match TexturePreset::ALL
+        .into_iter()
+        .find(|&p| &Texture::preset(p) == texture)
+    {
+        Some(p) => p.to_string(),
+        None => "custom".to_string(),
+    }
+}
+
+/// the preset a theme comes from
+fn theme_name(theme: &Theme) -> String {
+    match ThemePreset::ALL
+        .into_iter()
+        .find(|&p| &Theme::preset(p) == theme)
+    {
+        Some(p) => p.to_string(),
+        None => "custom".to_string(),
+    }
+}
+
+/// frames as m:ss.cc
+pub fn clock(frames: u64) -> String {
+    let centis = frames * 100 / FRAMES_PER_SECOND as u64;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Self {
+            screen: Screen::Main,
+            selected: 0,
+            scores: vec![],
+            saved: vec![],
+            unsaved: vec![],
+            pending: None,
+            name: String::new(),
+            place: None,
+            category: None,
+            reason: String::new(),
+        }
+    }
+
+    fn open(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.selected = 0;
+    }
+
+    pub fn play(&mut self, t: &mut Tetris) {
+        t.reset();
+        t.start();
+        self.open(Screen::Playing);
+    }
+
+    /// the running game ended: keep its result and show the game-over screen
+    pub fn game_over(&mut self, t: &Tetris) {
+        if let GameStatus::Exit(ref e) = t.status {
+            self.reason = e.clone();
+        }
+        self.scores.push((t.cfg.mode, t.record, t.frames));
+        self.place = None;
+        self.open(Screen::GameOver);
+    }
+
+    /// keep the result of a finished game. a record asks for a name first.
+    pub fn add_score(&mut self, score: Score) {
+        match scores::place(&self.saved, &score, TABLE_SIZE) {
+            Some(place) => {
+                if self.name.is_empty() {
+                    self.name = score.name.clone();
+                }
+                self.pending = Some((score, place));
+                self.open(Screen::Name);
+            }
+            None => {
+                self.saved.push(score.clone());
+                self.unsaved.push(score);
+            }
+        }
+    }
+
+    /// a key on the name screen, false for keys that aren't typing
+    pub fn type_key(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(c) if self.name.chars().count() < NAME_LEN => self.name.push(c),
+            Key::Space if self.name.chars().count() < NAME_LEN => self.name.push(' '),
+            Key::Char(_) | Key::Space => {}
+            Key::Backspace => {
+                self.name.pop();
+            }
+            Key::Enter | Key::Esc => self.finish_name(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// keep the waiting record under the typed name and show the game-over screen
+    pub fn finish_name(&mut self) {
+        if let Some((mut score, place)) = self.pending.take() {
+            let name = self.name.trim();
+            if !name.is_empty() {
+                score.name = name.to_string();
+            }
+            self.place = Some(place);
+            self.saved.push(score.clone());
+            self.unsaved.push(score);
+        }
+        self.open(Screen::GameOver);
+    }
+
+    /// the kept games to write to the scores file
+    pub fn take_unsaved(&mut self) -> Vec<Score> {
+        std::mem::take(&mut self.unsaved)
+    }
+
+    /// handle a key press on a menu screen. returns true when the player wants to quit.
+    pub fn handle(&mut self, t: &mut Tetris, signal: Signal) -> bool {
+        let len = match self.screen {
+            Screen::Main => MAIN_ITEMS,
+            Screen::Settings => SETTINGS_ITEMS,
+            Screen::GameOver => GAME_OVER_ITEMS,
+            Screen::Scores | Screen::Name | Screen::Playing => 1,
+        };
+        match signal {
+            Signal::Quit => return true,
+            Signal::Rotate => self.selected = (self.selected + len - 1) % len,
+            Signal::Accelerate => self.selected = (self.selected + 1) % len,
+            Signal::Left => self.change(t, -1),
+            Signal::Right => self.change(t, 1),
+            Signal::Pause if self.screen != Screen::Main => self.open(Screen::Main),
+            Signal::Confirm | Signal::Sink => return self.confirm(t),
+            _ => {}
+        }
+        false
+    }
+
+    fn confirm(&mut self, t: &mut Tetris) -> bool {
+        match (self.screen, self.selected) {
+            (Screen::Main, 0 | 1) => self.play(t),
+            (Screen::Main, 2) => {
+                self.category = Some(Category::of(&t.cfg));
+                self.open(Screen::Scores)
+            }
+            (Screen::Main, 3) => self.open(Screen::Settings),
+            (Screen::Main, _) => return true,
+            (Screen::Settings, i) if i == SETTINGS_ITEMS - 1 => self.open(Screen::Main),
+            (Screen::Settings, _) => self.change(t, 1),
+            (Screen::GameOver, 0) => self.play(t),
+            (Screen::GameOver, 1) => self.open(Screen::Main),
+            (Screen::GameOver, _) => return true,
+            (Screen::Scores, _) => self.open(Screen::Main),
+            (Screen::Name, _) => self.finish_name(),
+            (Screen::Playing, _) => {}
+        }
+        false
+    }
+
+    /// left/right on the selected line
+    fn change(&mut self, t: &mut Tetris, step: isize) {
+        let cfg = &mut t.cfg;
+        match (self.screen, self.selected) {
+            (Screen::Main, 0) => cfg.mode = cycle(&Mode::ALL, cfg.mode, step),
+            (Screen::Settings, 0) => {
+                cfg.start_level = cfg.start_level.saturating_add_signed(step).clamp(1, 20)
+            }
+            (Screen::Settings, 1) => cfg.accelerate = !cfg.accelerate,
+            (Screen::Settings, 2) => cfg.hold = !cfg.hold,
+            (Screen::Settings, 3) => cfg.feature_brick = !cfg.feature_brick,
+            (Screen::Settings, 4) => {
+                cfg.randomizer = cycle(&RandomizerKind::ALL, cfg.randomizer, step)
+            }
+            (Screen::Settings, 5) => cfg.scoring = cycle(&ScoringKind::ALL, cfg.scoring, step),
+            (Screen::Settings, 6) => {
+                let now = TexturePreset::ALL
+                    .into_iter()
+                    .find(|&p| Texture::preset(p) == cfg.texture)
+                    .unwrap_or_default();
+                cfg.texture = Texture::preset(cycle(&TexturePreset::ALL, now, step));
+            }
+            (Screen::Settings, 7) => {
+                let now = ThemePreset::ALL
+                    .into_iter()
+                    .find(|&p| Theme::preset(p) == cfg.theme)
+                    .unwrap_or_default();
+                cfg.theme = Theme::preset(cycle(&ThemePreset::ALL, now, step));
+            }
+            (Screen::Settings, 8) => cfg.width = cfg.width.saturating_add_signed(step).clamp(6, 20),
+            (Screen::Settings, 9) => {
+                cfg.height = cfg.height.saturating_add_signed(step).clamp(10, 30)
+            }
+            (Screen::Scores, _) => {
+                // 在有成绩的分类间切换, 当前设置的分类也在其中
+                let now = self.category.unwrap_or_else(|| Category::of(cfg));
+                let mut all = scores::categories(&self.saved);
+                if !all.contains(&now) {
+                    all.insert(0, now);
+                }
+                self.category = Some(cycle(&all, now, step));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn view(&self, t: &Tetris) -> MenuView {
+        let cfg = &t.cfg;
+        let (title, text, items) = match self.screen {
+            Screen::Main => (
+                "TETRIS-RS",
+                cfg.mode.description().to_string(),
+                vec![
+                    format!("mode: < {} >", cfg.mode),
+                    "start".to_string(),
+                    "high scores".to_string(),
+                    "settings".to_string(),
+                    "quit".to_string(),
+                ],
+            ),
+            Screen::Settings => (
+                "SETTINGS",
+                format!(
+                    "{}, {} to change",
+                    cfg.keys.names(Control::Left),
+                    cfg.keys.names(Control::Right)
+                ),
+                vec![
+                    format!("start level: {}", cfg.start_level),
+                    format!("level up: {}", on_off(cfg.accelerate)),
+                    format!("hold: {}", on_off(cfg.hold)),
+                    format!("feature bricks: {}", on_off(cfg.feature_brick)),
+                    format!("randomizer: {}", cfg.randomizer),
+                    format!("scoring: {}", cfg.scoring),
+                    format!("texture: {}", texture_name(&cfg.texture)),
+                    format!("theme: {}", theme_name(&cfg.theme)),
+                    format!("width: {}", cfg.width),
+                    format!("height: {}", cfg.height),
+                    "back".to_string(),
+                ],
+            ),
+            Screen::Scores => {
+                let category = self.category.unwrap_or_else(|| Category::of(cfg));
+                (
+                    "HIGH SCORES",
+                    self.table_text(category),
+                    vec!["back".to_string()],
+                )
+            }
+            Screen::Name => {
+                let text = match &self.pending {
+                    Some((score, place)) => {
+                        format!("#{} in {}\n\nname: {}_", place, score.category(), self.name)
+                    }
+                    None => String::new(),
+                };
+                ("NEW RECORD", text, vec![])
+            }
+            Screen::GameOver => {
+                let (mode, r, frames) = self.scores.last().copied().unwrap_or_default();
+                let record = match self.place {
+                    Some(place) => format!("new record: #{}\n", place),
+                    None => String::new(),
+                };
+                let text = format!(
+                    "{}\n{}\nmode: {}\nscore: {}\nlevel: {}\nrows: {}\nhighest combo: {}\nt-spins: {}\nperfect clears: {}\ntime: {}",
+                    self.reason,
+                    record,
+                    mode,
+                    r.score,
+                    r.level,
+                    r.eliminate_rows,
+                    r.high_combo,
+                    r.tspins + r.tspin_minis,
+                    r.perfect_clears,
+                    clock(frames)
+                );
+                (
+                    "GAME OVER",
+                    text,
+                    vec![
+                        "retry".to_string(),
+                        "main menu".to_string(),
+                        "quit".to_string(),
+                    ],
+                )
+            }
+            Screen::Playing => ("", String::new(), vec![]),
+        };
+        MenuView {
+            title: title.to_string(),
+            text,
+            items,
+            selected: self.selected,
+            hint: match self.screen {
+                Screen::Name => "type your name, enter to save".to_string(),
+                _ => format!(
+                    "{}, {} to choose, {} to confirm, {} to go back",
+                    cfg.keys.names(Control::Rotate),
+                    cfg.keys.names(Control::SoftDrop),
+                    cfg.keys.names(Control::Confirm),
+                    cfg.keys.names(Control::Pause)
+                ),
+            },
+        }
+    }
+
+    fn table_text(&self, category: Category) -> String {
+        let mut text = format!("< {} >\n\n", category);
+        let table = scores::table(&self.saved, category, TABLE_SIZE);
+        if table.is_empty() {
+            text.push_str("no games saved yet");
+        }
+        for (i, s) in table.iter().enumerate() {
+            text.push_str(&score_line(i + 1, s));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// one line of a high score table
+pub fn score_line(place: usize, s: &Score) -> String {
+    format!(
+        "{:>2}. {:<12} {:>7} {:>4} rows  {}{}",
+        place,
+        s.name,
+        s.score,
+        s.rows,
+        clock(s.frames),
+        match s.complete || s.mode == Mode::Marathon {
+            true => "",
+            false => "  (unfinished)",
+        }
+    )
+}
+
+#[test]
+fn menu_test() {
+    let mut t = Tetris::new(tetris_rs::env::EnvConfig::default());
+    let mut m = Menu::new();
+    // change the mode and start
+    m.handle(&mut t, Signal::Right);
+    assert_eq!(t.cfg.mode, Mode::Sprint);
+    m.handle(&mut t, Signal::Confirm);
+    assert_eq!(m.screen, Screen::Playing);
+    assert!(t.is_running());
+
+    t.event_quit();
+    m.game_over(&t);
+    assert_eq!(m.screen, Screen::GameOver);
+    assert!(m.view(&t).text.starts_with("keyboard quit"));
+    // retry starts a fresh game
+    m.handle(&mut t, Signal::Confirm);
+    assert!(t.is_running());
+    assert_eq!(m.scores.len(), 1);
+
+    // a record asks for a name before it is kept, sprints count when finished
+    m.add_score(Score {
+        complete: true,
+        frames: 5000,
+        ..Score::new(&t, "player")
+    });
+    assert_eq!(m.screen, Screen::Name);
+    m.type_key(Key::Backspace);
+    m.type_key(Key::Char('X'));
+    m.type_key(Key::Enter);
+    assert_eq!(m.screen, Screen::GameOver);
+    let saved = m.take_unsaved();
+    assert_eq!(saved[0].name, "playeX");
+    assert!(m.view(&t).text.contains("new record: #1"));
+    // worse than the record: kept right away
+    m.add_score(Score {
+        complete: false,
+        ..Score::new(&t, "player")
+    });
+    assert_eq!(m.screen, Screen::GameOver);
+    assert_eq!(m.take_unsaved().len(), 1);
+
+    // up from the first item wraps to quit
+    m.open(Screen::Main);
+    m.handle(&mut t, Signal::Rotate);
+    assert!(m.handle(&mut t, Signal::Confirm));
+}
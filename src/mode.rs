@@ -0,0 +1,62 @@
+//! Game modes: what ends a game besides topping out.
+
+use crate::{game::FRAMES_PER_SECOND, record::Record};
+
+/// rows to clear in sprint
+pub const SPRINT_LINES: usize = 40;
+/// length of an ultra game in frames (2 minutes)
+pub const ULTRA_FRAMES: u64 = 2 * 60 * FRAMES_PER_SECOND as u64;
+
+#[derive(
+    strum_macros::EnumString,
+    strum_macros::Display,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Default,
+    Hash,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum Mode {
+    /// play until the stack tops out
+    #[default]
+    Marathon,
+    /// clear `SPRINT_LINES` rows as fast as possible
+    Sprint,
+    /// score as much as possible in `ULTRA_FRAMES`
+    Ultra,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Marathon, Mode::Sprint, Mode::Ultra];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Mode::Marathon => "play until the stack tops out",
+            Mode::Sprint => "clear 40 rows as fast as possible",
+            Mode::Ultra => "score as much as possible in 2 minutes",
+        }
+    }
+
+    /// the goal of the mode is reached after `frames` frames
+    pub fn is_complete(self, record: &Record, frames: u64) -> bool {
+        match self {
+            Mode::Marathon => false,
+            Mode::Sprint => record.eliminate_rows >= SPRINT_LINES,
+            Mode::Ultra => frames >= ULTRA_FRAMES,
+        }
+    }
+}
+
+#[test]
+fn mode_test() {
+    let mut r = Record::new();
+    assert!(!Mode::Sprint.is_complete(&r, 0));
+    r.eliminate_rows = 40;
+    assert!(Mode::Sprint.is_complete(&r, 0));
+    assert!(!Mode::Marathon.is_complete(&r, u64::MAX));
+    assert!(Mode::Ultra.is_complete(&r, ULTRA_FRAMES));
+    assert_eq!("sprint".parse::<Mode>(), Ok(Mode::Sprint));
+}
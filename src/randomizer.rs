@@ -0,0 +1,244 @@
+//! Piece generators.
+//!
+//! `Tetris` asks its `Randomizer` for the next brick type whenever the preview
+//! queue needs refilling. The built-in generators are selected with
+//! `RandomizerKind` (env `RANDOMIZER`), custom ones can be passed to
+//! `Tetris::with_randomizer`.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use strum::IntoEnumIterator;
+
+use crate::bricks::BrickType;
+
+pub trait Randomizer: std::fmt::Debug + Send {
+    fn next(&mut self) -> BrickType;
+
+    fn clone_box(&self) -> Box<dyn Randomizer>;
+}
+
+impl Clone for Box<dyn Randomizer> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// the brick types a game can deal: the 7 classic ones, plus the feature bricks if enabled
+pub fn pool(feature_brick: bool) -> Vec<BrickType> {
+    BrickType::iter()
+        .filter(|bt| feature_brick || !bt.is_featured())
+        .collect()
+}
+
+fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(s) => StdRng::seed_from_u64(s),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+pub enum RandomizerKind {
+    /// every brick of the pool once per bag (7-bag, or 12-bag with feature bricks)
+    #[default]
+    #[strum(to_string = "bag7", serialize = "7bag", serialize = "bag")]
+    Bag7,
+    /// two copies of every brick per bag
+    #[strum(to_string = "bag14", serialize = "14bag")]
+    Bag14,
+    /// classic NES: reroll once when the same brick comes twice in a row
+    #[strum(to_string = "nes", serialize = "classic")]
+    Nes,
+    /// TGM: remembers the last 4 bricks and rerolls up to 6 times to avoid them
+    #[strum(to_string = "tgm", serialize = "history4")]
+    Tgm,
+    /// every brick independently uniform
+    #[strum(to_string = "random", serialize = "uniform")]
+    Random,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 5] = [
+        RandomizerKind::Bag7,
+        RandomizerKind::Bag14,
+        RandomizerKind::Nes,
+        RandomizerKind::Tgm,
+        RandomizerKind::Random,
+    ];
+
+    pub fn build(self, feature_brick: bool, seed: Option<u64>) -> Box<dyn Randomizer> {
+        let pool = pool(feature_brick);
+        let rng = rng(seed);
+        match self {
+            RandomizerKind::Bag7 => Box::new(Bag::new(pool, 1, rng)),
+            RandomizerKind::Bag14 => Box::new(Bag::new(pool, 2, rng)),
+            RandomizerKind::Nes => Box::new(Nes::new(pool, rng)),
+            RandomizerKind::Tgm => Box::new(Tgm::new(pool, rng)),
+            RandomizerKind::Random => Box::new(Uniform::new(pool, rng)),
+        }
+    }
+}
+
+/// shuffled bag holding `copies` of every brick of the pool
+#[derive(Debug, Clone)]
+pub struct Bag {
+    pool: Vec<BrickType>,
+    copies: usize,
+    bag: Vec<BrickType>,
+    rng: StdRng,
+}
+
+impl Bag {
+    pub fn new(pool: Vec<BrickType>, copies: usize, rng: StdRng) -> Self {
+        Self {
+            pool,
+            copies,
+            bag: vec![],
+            rng,
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self) -> BrickType {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend_from_slice(&self.pool);
+            }
+            self.bag.shuffle(&mut self.rng);
+        }
+        self.bag.pop().unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Nes {
+    pool: Vec<BrickType>,
+    last: Option<BrickType>,
+    rng: StdRng,
+}
+
+impl Nes {
+    pub fn new(pool: Vec<BrickType>, rng: StdRng) -> Self {
+        Self {
+            pool,
+            last: None,
+            rng,
+        }
+    }
+}
+
+impl Randomizer for Nes {
+    fn next(&mut self) -> BrickType {
+        // one extra "reroll" slot like the original: an 8-sided die for 7 bricks
+        let roll = self.rng.gen_range(0..=self.pool.len());
+        let mut bt = self.pool.get(roll).copied();
+        if bt.is_none() || bt == self.last {
+            bt = self.pool.choose(&mut self.rng).copied();
+        }
+        self.last = bt;
+        bt.unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tgm {
+    pool: Vec<BrickType>,
+    history: [BrickType; 4],
+    first: bool,
+    rng: StdRng,
+}
+
+impl Tgm {
+    const ROLLS: usize = 6;
+
+    pub fn new(pool: Vec<BrickType>, rng: StdRng) -> Self {
+        Self {
+            pool,
+            history: [BrickType::Z, BrickType::S, BrickType::S, BrickType::Z],
+            first: true,
+            rng,
+        }
+    }
+}
+
+impl Randomizer for Tgm {
+    fn next(&mut self) -> BrickType {
+        let bt = if self.first {
+            // never start with a brick that forces an overhang
+            self.first = false;
+            *[BrickType::I, BrickType::J, BrickType::L, BrickType::T]
+                .choose(&mut self.rng)
+                .unwrap()
+        } else {
+            let mut bt = *self.pool.choose(&mut self.rng).unwrap();
+            for _ in 1..Self::ROLLS {
+                if !self.history.contains(&bt) {
+                    break;
+                }
+                bt = *self.pool.choose(&mut self.rng).unwrap();
+            }
+            bt
+        };
+        self.history.rotate_right(1);
+        self.history[0] = bt;
+        bt
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Uniform {
+    pool: Vec<BrickType>,
+    rng: StdRng,
+}
+
+impl Uniform {
+    pub fn new(pool: Vec<BrickType>, rng: StdRng) -> Self {
+        Self { pool, rng }
+    }
+}
+
+impl Randomizer for Uniform {
+    fn next(&mut self) -> BrickType {
+        *self.pool.choose(&mut self.rng).unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn bag_test() {
+    let mut r = RandomizerKind::Bag7.build(false, Some(7));
+    for _ in 0..3 {
+        let mut bag: Vec<BrickType> = (0..7).map(|_| r.next()).collect();
+        bag.sort();
+        assert_eq!(bag, pool(false));
+    }
+
+    // feature bricks join the bag: 12-bag
+    let mut r = RandomizerKind::Bag7.build(true, Some(7));
+    let mut bag: Vec<BrickType> = (0..12).map(|_| r.next()).collect();
+    bag.sort();
+    assert_eq!(bag, pool(true));
+
+    // same seed, same sequence
+    let mut a = RandomizerKind::Tgm.build(false, Some(1));
+    let mut b = RandomizerKind::Tgm.build(false, Some(1));
+    assert!((0..50).all(|_| a.next() == b.next()));
+    assert_eq!(RandomizerKind::Bag7.to_string(), "bag7");
+}
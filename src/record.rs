@@ -1,21 +1,39 @@
+use crate::spin::{LineClear, TSpin};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Record {
     pub score: i64,
     pub combo: usize, //连击数量
     pub high_combo: usize,
     pub eliminate_rows: usize,
+    pub level: usize,
+    pub tspins: usize,
+    pub tspin_minis: usize,
+    pub perfect_clears: usize,
+    /// the last clear was a difficult one (tetris or T-spin), the next one gets the bonus
+    pub back_to_back: bool,
 }
 
 impl std::fmt::Display for Record {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "score: {}, highest combo: {}, rows eliminated: {}",
-            self.score, self.high_combo, self.eliminate_rows
+            "score: {}, level: {}, highest combo: {}, rows eliminated: {}, t-spins: {}",
+            self.score,
+            self.level,
+            self.high_combo,
+            self.eliminate_rows,
+            self.tspins + self.tspin_minis
         )
     }
 }
 
+impl Default for Record {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Record {
     pub fn new() -> Self {
         Self {
@@ -23,22 +41,28 @@ impl Record {
             combo: 0,
             eliminate_rows: 0,
             high_combo: 0,
+            level: 1,
+            tspins: 0,
+            tspin_minis: 0,
+            perfect_clears: 0,
+            back_to_back: false,
         }
     }
 
-    pub fn compute(&mut self, rows_num: usize) {
-        if rows_num == 0 {
-            self.combo = 0;
-            return;
+    /// bookkeeping shared by every scoring ruleset: rows, T-spins and perfect clears
+    pub fn count(&mut self, clear: LineClear) {
+        self.eliminate_rows += clear.lines;
+        match clear.tspin {
+            TSpin::Full => self.tspins += 1,
+            TSpin::Mini => self.tspin_minis += 1,
+            TSpin::None => {}
         }
-        for _ in 0..rows_num {
-            self.combo_once()
+        if clear.perfect {
+            self.perfect_clears += 1;
         }
     }
 
-    fn combo_once(&mut self) {
-        self.score += 200 + (self.combo * 60) as i64;
-        self.eliminate_rows += 1;
+    pub fn combo_up(&mut self) {
         self.combo += 1;
         // 计算历史最高连击
         if self.combo > self.high_combo {
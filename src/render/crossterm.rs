@@ -0,0 +1,138 @@
+//! crossterm backend, also works on Windows consoles
+
+use std::io::{self, stdout, Stdout, Write};
+
+use ::crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    queue,
+    style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use colored::Color;
+
+use super::{diff, Cell, PaintBoard, Renderer};
+use crate::theme::{ColorDepth, DepthColor};
+
+pub struct CrosstermRenderer<W: Write> {
+    out: W,
+    /// what is on screen now
+    front: Vec<Vec<Cell>>,
+    /// raw mode and the alternate screen are ours to restore
+    session: bool,
+    /// colors are downgraded to what the terminal can show
+    depth: ColorDepth,
+}
+
+impl CrosstermRenderer<Stdout> {
+    /// take over the terminal for the session: raw mode and the alternate screen,
+    /// both restored when the renderer is dropped
+    pub fn stdout() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = stdout();
+        queue!(out, EnterAlternateScreen)?;
+        let mut r = Self::new(out)?;
+        r.session = true;
+        Ok(r)
+    }
+}
+
+impl<W: Write> CrosstermRenderer<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        queue!(out, Hide, Clear(ClearType::All))?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            front: vec![],
+            session: false,
+            depth: ColorDepth::detect(),
+        })
+    }
+
+    /// use `depth` instead of the detected color depth
+    pub fn with_depth(mut self, depth: ColorDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    fn color(&self, color: Color) -> Option<style::Color> {
+        match self.depth.convert(color)? {
+            DepthColor::Named(c) => Some(crossterm_color(c)),
+            DepthColor::Indexed(i) => Some(style::Color::AnsiValue(i)),
+        }
+    }
+}
+
+/// the ANSI color `colored` would print
+pub fn crossterm_color(color: Color) -> style::Color {
+    match color {
+        Color::Black => style::Color::Black,
+        Color::Red => style::Color::DarkRed,
+        Color::Green => style::Color::DarkGreen,
+        Color::Yellow => style::Color::DarkYellow,
+        Color::Blue => style::Color::DarkBlue,
+        Color::Magenta => style::Color::DarkMagenta,
+        Color::Cyan => style::Color::DarkCyan,
+        Color::White => style::Color::Grey,
+        Color::BrightBlack => style::Color::DarkGrey,
+        Color::BrightRed => style::Color::Red,
+        Color::BrightGreen => style::Color::Green,
+        Color::BrightYellow => style::Color::Yellow,
+        Color::BrightBlue => style::Color::Blue,
+        Color::BrightMagenta => style::Color::Magenta,
+        Color::BrightCyan => style::Color::Cyan,
+        Color::BrightWhite => style::Color::White,
+        Color::TrueColor { r, g, b } => style::Color::Rgb { r, g, b },
+    }
+}
+
+impl<W: Write> Renderer for CrosstermRenderer<W> {
+    type Output = io::Result<()>;
+
+    fn draw(&mut self, frame: &PaintBoard) -> io::Result<()> {
+        let mut cursor = None;
+        let mut color = (None, None);
+        let mut changed = false;
+        for (x, y, cell) in diff(&self.front, &frame.0) {
+            if cursor != Some((x, y)) {
+                queue!(self.out, MoveTo(x as u16, y as u16))?;
+            }
+            if (cell.color, cell.bg) != color {
+                queue!(self.out, ResetColor)?;
+                if let Some(c) = cell.color.and_then(|c| self.color(c)) {
+                    queue!(self.out, SetForegroundColor(c))?;
+                }
+                if let Some(c) = cell.bg.and_then(|c| self.color(c)) {
+                    queue!(self.out, SetBackgroundColor(c))?;
+                }
+                color = (cell.color, cell.bg);
+            }
+            queue!(self.out, Print(cell.ch))?;
+            cursor = Some((x + 1, y));
+            changed = true;
+        }
+        if !changed {
+            return Ok(());
+        }
+        if color != (None, None) {
+            queue!(self.out, ResetColor)?;
+        }
+        self.front = frame.0.clone();
+        self.out.flush()
+    }
+
+    fn invalidate(&mut self) {
+        self.front.clear();
+        queue!(self.out, Clear(ClearType::All)).ok();
+    }
+}
+
+impl<W: Write> Drop for CrosstermRenderer<W> {
+    fn drop(&mut self) {
+        queue!(self.out, ResetColor, Show).ok();
+        if self.session {
+            queue!(self.out, LeaveAlternateScreen).ok();
+            terminal::disable_raw_mode().ok();
+        }
+        self.out.flush().ok();
+    }
+}
@@ -0,0 +1,675 @@
+//! Rendering: a read-only `View` of a game is painted into a `PaintBoard` of
+//! character cells, which a `Renderer` backend then outputs.
+//!
+//! - `PlainText` returns the frame as a `String` without escape codes, handy for tests and logs.
+//! - `TermionRenderer` and `CrosstermRenderer` (feature `terminal`) draw to the terminal,
+//!   writing only the cells that changed since the last frame.
+//! - `TetrisWidget` and friends (feature `ratatui`) render into a ratatui `Buffer`.
+
+use std::collections::VecDeque;
+
+use colored::{Color, Colorize};
+
+use crate::{
+    bricks::Brick,
+    game::{Board, Tetris},
+    keys::{Control, KeyBindings},
+    record::Record,
+    spin::{LineClear, TSpin},
+    texture::Texture,
+    theme::Theme,
+};
+
+#[cfg(feature = "terminal")]
+mod crossterm;
+mod plain;
+#[cfg(feature = "ratatui")]
+mod ratatui;
+#[cfg(feature = "terminal")]
+mod termion;
+
+#[cfg(feature = "terminal")]
+pub use self::crossterm::CrosstermRenderer;
+pub use self::plain::PlainText;
+#[cfg(feature = "ratatui")]
+pub use self::ratatui::{BoardWidget, HoldWidget, NextWidget, StatsWidget, TetrisWidget};
+#[cfg(feature = "terminal")]
+pub use self::termion::TermionRenderer;
+
+/// how long the last clear type stays on screen
+pub const FLASH_FRAMES: u64 = 90;
+
+/// everything a renderer may look at, borrowed from a `Tetris`
+#[derive(Debug, Clone)]
+pub struct View<'a> {
+    pub board: &'a Board,
+    /// board cells of the falling brick, may be above the board
+    pub brick: Vec<(isize, isize)>,
+    pub brick_color: Option<Color>,
+    pub shadow: Vec<(isize, isize)>,
+    /// None when hold is disabled
+    pub hold: Option<Option<&'a Brick>>,
+    pub can_hold: bool,
+    pub next: &'a VecDeque<Brick>,
+    pub record: &'a Record,
+    pub paused: bool,
+    pub last_clear: Option<(LineClear, u64)>,
+    pub frames: u64,
+    pub texture: &'a Texture,
+    pub theme: &'a Theme,
+    /// for the resume hint while paused
+    pub keys: &'a KeyBindings,
+    /// key help shown under the board, the bindings of the game by default
+    pub help: String,
+}
+
+impl<'a> View<'a> {
+    pub fn new(game: &'a Tetris) -> Self {
+        Self {
+            board: &game.board,
+            brick: game.get_absolute(),
+            brick_color: game.now_brick.as_ref().map(|b| b.color),
+            shadow: game.get_shadow(),
+            hold: game.cfg.hold.then_some(game.hold_brick.as_ref()),
+            can_hold: game.can_hold,
+            next: &game.following_bricks,
+            record: &game.record,
+            paused: game.is_paused(),
+            last_clear: game.last_clear,
+            frames: game.frames,
+            texture: &game.cfg.texture,
+            theme: &game.cfg.theme,
+            keys: &game.cfg.keys,
+            help: game.cfg.keys.help(),
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = help.into();
+        self
+    }
+}
+
+impl<'a> From<&'a Tetris> for View<'a> {
+    fn from(game: &'a Tetris) -> Self {
+        Self::new(game)
+    }
+}
+
+/// one character cell of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub color: Option<Color>,
+    /// background, only used by the compact half-block board
+    pub bg: Option<Color>,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            color: None,
+            bg: None,
+        }
+    }
+}
+
+/// a frame being painted: rows of cells and the filler for cells not painted yet
+#[derive(Debug, Clone)]
+pub struct PaintBoard(pub Vec<Vec<Cell>>, pub Cell);
+
+impl std::fmt::Display for PaintBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut result = String::new();
+        for row in &self.0 {
+            for c in row {
+                let mut text = c.ch.to_string().normal();
+                if let Some(color) = c.color {
+                    text = text.color(color);
+                }
+                if let Some(bg) = c.bg {
+                    text = text.on_color(bg);
+                }
+                result.push_str(&text.to_string());
+            }
+            result.push('\n');
+        }
+
+        write!(f, "{}", result)
+    }
+}
+
+impl PaintBoard {
+    pub fn new(width: usize, height: usize, empty: char) -> Self {
+        let empty = Cell {
+            ch: empty,
+            ..Default::default()
+        };
+        Self(vec![vec![empty; width + 10]; height], empty)
+    }
+    pub fn paint_pixel(&mut self, x: usize, y: usize, pixel: char, color: Option<Color>) {
+        self.paint_cell(
+            x,
+            y,
+            Cell {
+                ch: pixel,
+                color,
+                bg: None,
+            },
+        );
+    }
+    pub fn paint_cell(&mut self, x: usize, y: usize, cell: Cell) {
+        let width = self.0.first().map_or(0, |row| row.len());
+        let height = self.0.len();
+        if y >= height {
+            for _ in height..=y {
+                self.0.push(vec![self.1; width]);
+            }
+        }
+        if x >= width {
+            for i in 0..self.0.len() {
+                for _ in width..=x {
+                    self.0[i].push(self.1);
+                }
+            }
+        }
+        self.0[y][x] = cell;
+    }
+    /// columns and rows painted so far
+    pub fn size(&self) -> (usize, usize) {
+        (self.0.first().map_or(0, |row| row.len()), self.0.len())
+    }
+    /// copy every cell of `other` with its top left corner at `(x, y)`
+    pub fn blit(&mut self, other: &PaintBoard, x: usize, y: usize) {
+        for (dy, row) in other.0.iter().enumerate() {
+            for (dx, c) in row.iter().enumerate() {
+                self.paint_cell(x + dx, y + dy, *c);
+            }
+        }
+    }
+    pub fn paint_string(&mut self, x: usize, y: usize, s: &str, color: Option<Color>) {
+        let mut x_var = x;
+        let mut y_var = y;
+        for c in s.chars() {
+            if c == '\n' {
+                y_var += 1;
+                x_var = x;
+                continue;
+            }
+            self.paint_pixel(x_var, y_var, c, color);
+            x_var += 1;
+        }
+    }
+}
+
+/// `(x, y, cell)` of every cell that differs between two frames.
+/// cells outside a frame count as blank, so a smaller frame clears what is left over.
+pub fn diff<'a>(
+    front: &'a [Vec<Cell>],
+    back: &'a [Vec<Cell>],
+) -> impl Iterator<Item = (usize, usize, Cell)> + 'a {
+    let row_len = |cells: &[Vec<Cell>], y: usize| cells.get(y).map_or(0, |row| row.len());
+    let cell = |cells: &[Vec<Cell>], x: usize, y: usize| {
+        cells
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or_default()
+    };
+    (0..back.len().max(front.len())).flat_map(move |y| {
+        (0..row_len(back, y).max(row_len(front, y))).filter_map(move |x| {
+            let new = cell(back, x, y);
+            (cell(front, x, y) != new).then_some((x, y, new))
+        })
+    })
+}
+
+pub trait Renderer {
+    type Output;
+
+    /// output a painted frame
+    fn draw(&mut self, frame: &PaintBoard) -> Self::Output;
+
+    /// forget what is on screen so the next frame is drawn in full, e.g. after a resize
+    fn invalidate(&mut self) {}
+
+    /// paint the game with the standard layout and output it
+    fn render(&mut self, view: &View) -> Self::Output {
+        self.draw(&paint(view))
+    }
+}
+
+/// a brick in its spawn orientation, as lines of text
+pub fn brick_text(brick: &Brick, texture: &Texture) -> String {
+    let (min_x, max_x, min_y, max_y) = brick.limits();
+    let full = texture.cell(&texture.full);
+    let empty = texture.cell(&texture.empty);
+    let mut result = String::new();
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            if brick.pixels.contains(&(x, y)) {
+                result.push_str(&full);
+            } else {
+                result.push_str(&empty);
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// the standard layout: board on the left, stats, hold and next queue on the right
+pub fn paint(view: &View) -> PaintBoard {
+    let (bw, bh) = board_size(view);
+    let mut painter = PaintBoard::new(0, 0, ' ');
+    paint_board(&mut painter, view, 0, 0);
+    // 绘制介绍
+    painter.paint_string(0, bh, &view.help, view.theme.accent);
+    paint_stats(&mut painter, view, bw + 3, 1);
+    let mut start_y = 6;
+    start_y += paint_hold(&mut painter, view, bw + 3, start_y);
+    paint_next(&mut painter, view, bw + 3, start_y);
+    painter
+}
+
+/// columns reserved for the side panel
+const PANEL_WIDTH: usize = 20;
+
+/// how the game is laid out in a terminal of a given size, from roomy to cramped
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Fit {
+    /// panels right of the board, help below
+    Side,
+    /// panels right of the board, no help
+    SideNoHelp,
+    /// stats below the board, hold and next queue hidden
+    Below,
+    /// only the board
+    BoardOnly,
+    /// not even the board fits, it needs this many columns and rows
+    TooSmall(usize, usize),
+}
+
+/// the standard layout adapted to a terminal of `cols` x `rows` and centered in it.
+/// panels move below the board or disappear when there is no room, and when not even
+/// the board fits a message tells the size needed.
+pub fn paint_fit(view: &View, cols: usize, rows: usize) -> (PaintBoard, Fit) {
+    let mut board = PaintBoard::new(0, 0, ' ');
+    paint_board(&mut board, view, 0, 0);
+    let mut panel = PaintBoard::new(0, 0, ' ');
+    paint_stats(&mut panel, view, 0, 0);
+    let start_y = 5 + paint_hold(&mut panel, view, 0, 5);
+    paint_next(&mut panel, view, 0, start_y);
+    let mut stats = PaintBoard::new(0, 0, ' ');
+    paint_stats(&mut stats, view, 0, 0);
+    let mut help = PaintBoard::new(0, 0, ' ');
+    help.paint_string(0, 0, &view.help, view.theme.accent);
+
+    // 尺寸按最大的情况算, 避免布局随下一个方块或消除提示跳动
+    let (bw, bh) = board_size(view);
+    let hold_h = if view.hold.is_some() { 5 } else { 0 };
+    let pw = panel.size().0.max(PANEL_WIDTH);
+    let ph = 5 + hold_h + 2 + 4 * view.next.len();
+    let (sw, sh) = (stats.size().0.max(PANEL_WIDTH), 4);
+    let (hw, hh) = help.size();
+    let candidates = [
+        (Fit::Side, (bw + 3 + pw).max(hw), (bh + hh).max(ph + 1)),
+        (Fit::SideNoHelp, bw + 3 + pw, bh.max(ph + 1)),
+        (Fit::Below, bw.max(sw), bh + 1 + sh),
+        (Fit::BoardOnly, bw, bh),
+    ];
+    let found = candidates.iter().find(|&&(_, w, h)| w <= cols && h <= rows);
+    let (fit, w, h) = match found {
+        Some(&c) => c,
+        None => {
+            let mut frame = PaintBoard::new(0, 0, ' ');
+            let text = format!(
+                "terminal too small\nneed {}x{}, have {}x{}",
+                bw, bh, cols, rows
+            );
+            frame.paint_string(0, 0, &text, Some(Color::BrightRed));
+            return (frame, Fit::TooSmall(bw, bh));
+        }
+    };
+    let mut frame = PaintBoard::new(0, 0, ' ');
+    frame.blit(&board, 0, 0);
+    match fit {
+        Fit::Side => {
+            frame.blit(&panel, bw + 3, 1);
+            frame.blit(&help, 0, bh);
+        }
+        Fit::SideNoHelp => frame.blit(&panel, bw + 3, 1),
+        Fit::Below => frame.blit(&stats, 0, bh + 1),
+        _ => {}
+    }
+    let frame = center(&frame, (w, h), cols, rows);
+    (frame, fit)
+}
+
+/// move a frame of `size` to the middle of a `cols` x `rows` screen
+pub fn center(frame: &PaintBoard, size: (usize, usize), cols: usize, rows: usize) -> PaintBoard {
+    let mut centered = PaintBoard::new(0, 0, ' ');
+    centered.blit(
+        frame,
+        cols.saturating_sub(size.0) / 2,
+        rows.saturating_sub(size.1) / 2,
+    );
+    centered
+}
+
+/// columns and rows `paint_board` uses
+pub fn board_size(view: &View) -> (usize, usize) {
+    let (w, h) = (view.board.width, view.board.height);
+    match view.texture.compact {
+        true => (w + 2, h.div_ceil(2) + 3),
+        false => ((w + 2) * view.texture.width(), h + 3),
+    }
+}
+
+/// the walled board with the falling brick, its shadow and the last clear below,
+/// `board_size` from `(x, y)`
+pub fn paint_board(painter: &mut PaintBoard, view: &View, x0: usize, y0: usize) {
+    let texture = view.texture;
+    let theme = view.theme;
+    let (bw, bh) = board_size(view);
+    // 每个格子占的列数, 紧凑模式是一列
+    let cw = match texture.compact {
+        true => 1,
+        false => texture.width(),
+    };
+    // 棋盘内部占的行数, 紧凑模式两行并一行
+    let rows = bh - 3;
+    let w = view.board.width;
+    let wall = texture.cell(&texture.wall);
+    let empty = texture.cell(&texture.empty);
+    let wall = match texture.compact {
+        true => wall.chars().next().unwrap_or(' ').to_string(),
+        false => wall,
+    };
+    let empty = match texture.compact {
+        true => empty.chars().next().unwrap_or(' ').to_string(),
+        false => empty,
+    };
+
+    // 绘制墙
+    // 横墙
+    for i in 0..w + 2 {
+        painter.paint_string(x0 + i * cw, y0, &"+".repeat(cw), theme.wall);
+        painter.paint_string(x0 + i * cw, y0 + rows + 1, &wall, theme.wall);
+    }
+    //竖墙
+    for j in 0..rows + 2 {
+        painter.paint_string(x0, y0 + j, &wall, theme.wall);
+        painter.paint_string(x0 + (w + 1) * cw, y0 + j, &wall, theme.wall);
+    }
+
+    // 暂停时盖住棋盘, 不让玩家借暂停观察局面
+    if view.paused {
+        for y in 0..rows {
+            for x in 0..w {
+                painter.paint_string(x0 + (x + 1) * cw, y0 + y + 1, &empty, None);
+            }
+        }
+        let resume = format!("{} resume", view.keys.names(Control::Pause));
+        for (i, text) in ["PAUSED", &resume].iter().enumerate() {
+            let x = bw.saturating_sub(text.len()) / 2;
+            let color = [Color::BrightYellow, Color::BrightBlack][i];
+            painter.paint_string(x0 + x.max(1), y0 + rows / 2 + i * 2, text, Some(color));
+        }
+    } else if texture.compact {
+        paint_half_blocks(painter, view, x0 + 1, y0 + 1, &empty);
+    } else {
+        let full = texture.cell(&texture.full);
+        let shadow = texture.cell(&texture.shadow);
+        // 绘制元素
+        for (y, line) in view.board.datas.iter().enumerate() {
+            for (x, unit) in line.iter().enumerate() {
+                let (text, color) = match unit.0 {
+                    Some(color) => (&full, Some(color)),
+                    None => (&empty, None),
+                };
+                painter.paint_string(x0 + (x + 1) * cw, y0 + y + 1, text, color);
+            }
+        }
+        // 绘制影子
+        for &(x, y) in &view.shadow {
+            if y >= 0 {
+                painter.paint_string(
+                    x0 + (x as usize + 1) * cw,
+                    y0 + y as usize + 1,
+                    &shadow,
+                    theme.ghost,
+                );
+            }
+        }
+        // 绘制本体
+        for &(x, y) in &view.brick {
+            if y >= 0 {
+                painter.paint_string(
+                    x0 + (x as usize + 1) * cw,
+                    y0 + y as usize + 1,
+                    &full,
+                    view.brick_color,
+                );
+            }
+        }
+    }
+    // 闪烁显示刚才的消除类型
+    if let Some((clear, at)) = view.last_clear {
+        let age = view.frames - at;
+        if age < FLASH_FRAMES && (age / 10).is_multiple_of(2) {
+            let color = match clear.tspin {
+                TSpin::None => Color::Yellow,
+                _ => Color::Magenta,
+            };
+            let text = clear.to_string().to_uppercase();
+            painter.paint_string(x0 + cw, y0 + rows + 2, &text, Some(color));
+        }
+    }
+}
+
+/// compact mode: two board rows per terminal row, the upper one in the foreground
+/// color of `▀` and the lower one in its background
+fn paint_half_blocks(painter: &mut PaintBoard, view: &View, x0: usize, y0: usize, empty: &str) {
+    let (w, h) = (view.board.width, view.board.height);
+    let mut grid: Vec<Vec<Option<Color>>> = view
+        .board
+        .datas
+        .iter()
+        .map(|line| line.iter().map(|unit| unit.0).collect())
+        .collect();
+    let mut put = |cells: &[(isize, isize)], color: Option<Color>| {
+        for &(x, y) in cells {
+            if y >= 0 && (y as usize) < h && x >= 0 && (x as usize) < w {
+                grid[y as usize][x as usize] = color;
+            }
+        }
+    };
+    put(&view.shadow, view.theme.ghost.or(Some(Color::BrightBlack)));
+    put(&view.brick, view.brick_color);
+    for y in 0..h.div_ceil(2) {
+        for x in 0..w {
+            let top = grid[2 * y][x];
+            let bottom = grid.get(2 * y + 1).and_then(|line| line[x]);
+            let cell = match (top, bottom) {
+                (None, None) => Cell {
+                    ch: empty.chars().next().unwrap_or(' '),
+                    ..Default::default()
+                },
+                (Some(t), None) => Cell {
+                    ch: '▀',
+                    color: Some(t),
+                    bg: None,
+                },
+                (None, Some(b)) => Cell {
+                    ch: '▄',
+                    color: Some(b),
+                    bg: None,
+                },
+                (Some(t), Some(b)) if t == b => Cell {
+                    ch: '█',
+                    color: Some(t),
+                    bg: None,
+                },
+                (Some(t), Some(b)) => Cell {
+                    ch: '▀',
+                    color: Some(t),
+                    bg: Some(b),
+                },
+            };
+            painter.paint_cell(x0 + x, y0 + y, cell);
+        }
+    }
+}
+
+/// score, level, combo and rows, 4 rows from `(x, y)`
+pub fn paint_stats(painter: &mut PaintBoard, view: &View, x: usize, y: usize) {
+    let r = view.record;
+    let stats = [
+        ("score: ", r.score.to_string(), Color::Red),
+        ("level: ", r.level.to_string(), Color::Green),
+        (
+            "highest combo: ",
+            r.high_combo.to_string(),
+            Color::BrightBlue,
+        ),
+        (
+            "combout rows: ",
+            r.eliminate_rows.to_string(),
+            Color::Yellow,
+        ),
+    ];
+    for (i, (label, value, color)) in stats.iter().enumerate() {
+        painter.paint_string(x, y + i, label, view.theme.text);
+        painter.paint_string(x + label.len(), y + i, value, Some(*color));
+    }
+}
+
+/// the hold box, greyed out while hold is used up. returns the rows used.
+pub fn paint_hold(painter: &mut PaintBoard, view: &View, x: usize, y: usize) -> usize {
+    let hold = match view.hold {
+        Some(hold) => hold,
+        None => return 0,
+    };
+    painter.paint_string(x, y, "hold:", view.theme.text);
+    if let Some(e) = hold.filter(|_| !view.paused) {
+        let color = match view.can_hold {
+            true => e.color,
+            false => Color::BrightBlack,
+        };
+        painter.paint_string(x + 2, y + 2, &brick_text(e, view.texture), Some(color));
+    }
+    5
+}
+
+/// the next queue, returns the rows used
+pub fn paint_next(painter: &mut PaintBoard, view: &View, x: usize, y: usize) -> usize {
+    painter.paint_string(x, y, "nexts:", view.theme.text);
+    let mut start_y = y + 2;
+    if view.paused {
+        return start_y - y;
+    }
+    for e in view.next.iter() {
+        painter.paint_string(x + 2, start_y, &brick_text(e, view.texture), Some(e.color));
+        start_y += e.get_size().1 + 1;
+    }
+    start_y - y
+}
+
+#[test]
+fn paint_board_test() {
+    let mut b = PaintBoard::new(5, 5, '-');
+    b.paint_pixel(40, 2, 'H', Some(Color::Magenta));
+    b.paint_string(4, 4, "Hello\nworld", Some(Color::Blue));
+    // 画到外面会把画板撑大
+    assert_eq!(b.size(), (41, 6));
+    assert_eq!(b.0[0][0].ch, '-');
+    assert_eq!(b.0[2][40].ch, 'H');
+    assert_eq!(b.0[2][40].color, Some(Color::Magenta));
+    let world: String = b.0[5][4..9].iter().map(|c| c.ch).collect();
+    assert_eq!(world, "world");
+    assert!(b.0[5][4..9].iter().all(|c| c.color == Some(Color::Blue)));
+}
+
+#[test]
+fn diff_test() {
+    let mut a = PaintBoard::new(2, 2, ' ');
+    a.paint_string(0, 0, "ab", None);
+    let mut b = a.clone();
+    assert_eq!(diff(&a.0, &b.0).count(), 0);
+    b.paint_string(3, 1, "xy", Some(Color::Red));
+    let changed: Vec<(usize, usize)> = diff(&a.0, &b.0).map(|(x, y, _)| (x, y)).collect();
+    assert_eq!(changed, [(3, 1), (4, 1)]);
+    // a smaller frame blanks what is left over
+    let c = PaintBoard::new(0, 0, ' ');
+    assert!(diff(&a.0, &c.0).all(|(_, y, cell)| y == 0 && cell == Cell::default()));
+}
+
+#[test]
+fn fit_test() {
+    use crate::{env::EnvConfig, game::Tetris};
+
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    let view = View::new(&t);
+    assert_eq!(paint_fit(&view, 120, 40).1, Fit::Side);
+    assert_eq!(paint_fit(&view, 40, 40).1, Fit::SideNoHelp);
+    // 35x25 centered in 40x40
+    let (frame, _) = paint_fit(&view, 40, 40);
+    assert_eq!(frame.0[7][2].ch, 'H');
+    assert_eq!(frame.0[7][1].ch, ' ');
+    assert_eq!(paint_fit(&view, 30, 40).1, Fit::Below);
+    assert_eq!(paint_fit(&view, 12, 23).1, Fit::BoardOnly);
+    let (frame, fit) = paint_fit(&view, 12, 22);
+    assert_eq!(fit, Fit::TooSmall(12, 23));
+    let text = PlainText.draw(&frame);
+    assert!(text.contains("need 12x23, have 12x22"));
+}
+
+#[test]
+fn texture_render_test() {
+    use crate::{
+        env::EnvConfig,
+        game::Tetris,
+        texture::{Texture, TexturePreset},
+    };
+
+    let mut t = Tetris::new(EnvConfig {
+        texture: Texture::preset(TexturePreset::Blocks),
+        seed: Some(1),
+        ..Default::default()
+    });
+    t.start();
+    let color = t.now_brick.as_ref().map(|b| b.color);
+    t.event_sink();
+    let view = View::new(&t);
+    assert_eq!(board_size(&view), (24, 23));
+    let text = PlainText.render(&view);
+    let floor = text.lines().nth(21).unwrap();
+    assert!(floor.starts_with(&"▓".repeat(24)));
+    assert!(text.contains("██"));
+
+    t.cfg.texture = Texture::preset(TexturePreset::Compact);
+    let view = View::new(&t);
+    assert_eq!(board_size(&view), (12, 13));
+    let frame = paint(&view);
+    let cell = |ch, color| Cell {
+        ch,
+        color,
+        bg: None,
+    };
+    // 落下的砖块在最底下两行的第 4 列, 下一块的影子落在它上面
+    let row = |x, c| {
+        let mut row = vec![cell(' ', None); 12];
+        row[0] = cell('█', None);
+        row[11] = cell('█', None);
+        row[x] = c;
+        row
+    };
+    assert_eq!(frame.0[10][..12], row(5, cell('█', color)));
+    assert_eq!(
+        frame.0[9][..12],
+        row(5, cell('▄', Some(Color::BrightBlack)))
+    );
+}
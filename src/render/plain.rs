@@ -0,0 +1,58 @@
+//! plain-text backend: frames as strings without escape codes
+
+use super::{PaintBoard, Renderer};
+
+/// returns every frame as text, trailing blanks trimmed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainText;
+
+impl Renderer for PlainText {
+    type Output = String;
+
+    fn draw(&mut self, frame: &PaintBoard) -> String {
+        let mut text = String::new();
+        for row in &frame.0 {
+            let line: String = row.iter().map(|c| c.ch).collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[test]
+fn plain_text_test() {
+    use crate::{
+        env::EnvConfig,
+        game::Tetris,
+        keys::{Control, Key},
+        render::View,
+        texture::Texture,
+    };
+
+    let mut t = Tetris::new(EnvConfig {
+        width: 10,
+        height: 15,
+        texture: Texture {
+            wall: "0".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    t.start();
+    t.update();
+    let text = PlainText.render(&View::new(&t));
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "0++++++++++0");
+    assert_eq!(lines[1].split("   ").last(), Some("score: 0"));
+    assert!(lines[16].starts_with("000000000000"));
+    assert!(!text.contains('\x1b'));
+
+    t.pause();
+    let text = PlainText.render(&View::new(&t));
+    assert!(text.contains("PAUSED"));
+    assert!(text.contains("p/esc resume"));
+    t.cfg.keys.set(Control::Pause, vec![Key::Tab]);
+    let text = PlainText.render(&View::new(&t));
+    assert!(text.contains("tab resume"));
+}
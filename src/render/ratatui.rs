@@ -0,0 +1,164 @@
+//! ratatui widgets for embedding a game in other TUIs (feature `ratatui`).
+//!
+//! `TetrisWidget` draws the standard layout, `BoardWidget`, `NextWidget`,
+//! `HoldWidget` and `StatsWidget` draw one part each so apps can place them
+//! in their own layout. Parts that do not fit the area are clipped.
+
+use ::ratatui::{buffer::Buffer, layout::Rect, style, widgets::Widget};
+use colored::Color;
+
+use super::{paint, paint_board, paint_hold, paint_next, paint_stats, PaintBoard, View};
+use crate::game::Tetris;
+
+/// the ratatui color `colored` would print
+pub fn ratatui_color(color: Color) -> style::Color {
+    match color {
+        Color::Black => style::Color::Black,
+        Color::Red => style::Color::Red,
+        Color::Green => style::Color::Green,
+        Color::Yellow => style::Color::Yellow,
+        Color::Blue => style::Color::Blue,
+        Color::Magenta => style::Color::Magenta,
+        Color::Cyan => style::Color::Cyan,
+        Color::White => style::Color::Gray,
+        Color::BrightBlack => style::Color::DarkGray,
+        Color::BrightRed => style::Color::LightRed,
+        Color::BrightGreen => style::Color::LightGreen,
+        Color::BrightYellow => style::Color::LightYellow,
+        Color::BrightBlue => style::Color::LightBlue,
+        Color::BrightMagenta => style::Color::LightMagenta,
+        Color::BrightCyan => style::Color::LightCyan,
+        Color::BrightWhite => style::Color::White,
+        Color::TrueColor { r, g, b } => style::Color::Rgb(r, g, b),
+    }
+}
+
+/// copy a painted frame into `area`, clipping what does not fit
+fn blit(frame: &PaintBoard, area: Rect, buf: &mut Buffer) {
+    for (y, row) in frame.0.iter().enumerate().take(area.height as usize) {
+        for (x, c) in row.iter().enumerate().take(area.width as usize) {
+            if let Some(cell) = buf.cell_mut((area.x + x as u16, area.y + y as u16)) {
+                cell.set_char(c.ch);
+                cell.set_fg(c.color.map_or(style::Color::Reset, ratatui_color));
+                cell.set_bg(c.bg.map_or(style::Color::Reset, ratatui_color));
+            }
+        }
+    }
+}
+
+/// board, stats, hold and next queue in the standard layout
+pub struct TetrisWidget<'a> {
+    view: View<'a>,
+}
+
+impl<'a> TetrisWidget<'a> {
+    pub fn new(game: &'a Tetris) -> Self {
+        Self {
+            view: View::new(game),
+        }
+    }
+
+    pub fn view(view: View<'a>) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for TetrisWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        blit(&paint(&self.view), area, buf);
+    }
+}
+
+/// the walled board with the falling brick and its shadow
+pub struct BoardWidget<'a> {
+    view: &'a View<'a>,
+}
+
+impl<'a> BoardWidget<'a> {
+    pub fn new(view: &'a View<'a>) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for BoardWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut frame = PaintBoard::new(0, 0, ' ');
+        paint_board(&mut frame, self.view, 0, 0);
+        blit(&frame, area, buf);
+    }
+}
+
+pub struct NextWidget<'a> {
+    view: &'a View<'a>,
+}
+
+impl<'a> NextWidget<'a> {
+    pub fn new(view: &'a View<'a>) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for NextWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut frame = PaintBoard::new(0, 0, ' ');
+        paint_next(&mut frame, self.view, 0, 0);
+        blit(&frame, area, buf);
+    }
+}
+
+/// draws nothing when hold is disabled
+pub struct HoldWidget<'a> {
+    view: &'a View<'a>,
+}
+
+impl<'a> HoldWidget<'a> {
+    pub fn new(view: &'a View<'a>) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for HoldWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut frame = PaintBoard::new(0, 0, ' ');
+        paint_hold(&mut frame, self.view, 0, 0);
+        blit(&frame, area, buf);
+    }
+}
+
+/// score, level, combo and rows
+pub struct StatsWidget<'a> {
+    view: &'a View<'a>,
+}
+
+impl<'a> StatsWidget<'a> {
+    pub fn new(view: &'a View<'a>) -> Self {
+        Self { view }
+    }
+}
+
+impl Widget for StatsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut frame = PaintBoard::new(0, 0, ' ');
+        paint_stats(&mut frame, self.view, 0, 0);
+        blit(&frame, area, buf);
+    }
+}
+
+#[test]
+fn widget_test() {
+    use crate::env::EnvConfig;
+
+    let mut t = Tetris::new(EnvConfig::default());
+    t.start();
+    t.event_sink();
+    let mut buf = Buffer::empty(Rect::new(0, 0, 40, 30));
+    TetrisWidget::new(&t).render(buf.area, &mut buf);
+    assert_eq!(buf[(0, 0)].symbol(), "H");
+    assert_eq!(buf[(1, 0)].symbol(), "+");
+
+    // a small area clips instead of panicking
+    let view = View::new(&t);
+    let mut buf = Buffer::empty(Rect::new(2, 2, 5, 1));
+    StatsWidget::new(&view).render(buf.area, &mut buf);
+    assert_eq!(buf[(2, 2)].symbol(), "s");
+}
@@ -0,0 +1,135 @@
+//! termion/ANSI backend: diffs frames and writes only the changed cells
+
+use std::{
+    fmt::Write as _,
+    io::{self, stdout, StdoutLock, Write},
+};
+
+use ::termion::{
+    raw::{IntoRawMode, RawTerminal},
+    screen::{AlternateScreen, IntoAlternateScreen},
+};
+
+use super::{diff, Cell, PaintBoard, Renderer};
+use crate::theme::ColorDepth;
+
+pub struct TermionRenderer<W: Write> {
+    out: W,
+    /// what is on screen now
+    front: Vec<Vec<Cell>>,
+    /// colors are downgraded to what the terminal can show
+    depth: ColorDepth,
+}
+
+impl TermionRenderer<AlternateScreen<RawTerminal<StdoutLock<'static>>>> {
+    /// take over the terminal for the session: locked stdout, raw mode and the
+    /// alternate screen, all restored when the renderer is dropped
+    pub fn stdout() -> io::Result<Self> {
+        let out = stdout().lock().into_raw_mode()?.into_alternate_screen()?;
+        Self::new(out)
+    }
+}
+
+impl<W: Write> TermionRenderer<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        write!(out, "{}{}", ::termion::cursor::Hide, ::termion::clear::All)?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            front: vec![],
+            depth: ColorDepth::detect(),
+        })
+    }
+
+    /// use `depth` instead of the detected color depth
+    pub fn with_depth(mut self, depth: ColorDepth) -> Self {
+        self.depth = depth;
+        self
+    }
+}
+
+impl<W: Write> Renderer for TermionRenderer<W> {
+    type Output = io::Result<()>;
+
+    fn draw(&mut self, frame: &PaintBoard) -> io::Result<()> {
+        let mut buf = String::new();
+        // 光标位置和当前颜色, 相邻的格子不用重复移动光标和设置颜色
+        let mut cursor = None;
+        let mut color = (None, None);
+        for (x, y, cell) in diff(&self.front, &frame.0) {
+            if cursor != Some((x, y)) {
+                write!(
+                    buf,
+                    "{}",
+                    ::termion::cursor::Goto(x as u16 + 1, y as u16 + 1)
+                )
+                .ok();
+            }
+            if (cell.color, cell.bg) != color {
+                // 先复位再设置, 去掉上一个格子的前景或背景
+                buf.push_str("\x1b[0m");
+                if let Some(c) = cell.color.and_then(|c| self.depth.convert(c)) {
+                    write!(buf, "\x1b[{}m", c.sgr(false)).ok();
+                }
+                if let Some(c) = cell.bg.and_then(|c| self.depth.convert(c)) {
+                    write!(buf, "\x1b[{}m", c.sgr(true)).ok();
+                }
+                color = (cell.color, cell.bg);
+            }
+            buf.push(cell.ch);
+            cursor = Some((x + 1, y));
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        if color != (None, None) {
+            buf.push_str("\x1b[0m");
+        }
+        self.front = frame.0.clone();
+        self.out.write_all(buf.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn invalidate(&mut self) {
+        self.front.clear();
+        write!(self.out, "{}", ::termion::clear::All).ok();
+    }
+}
+
+impl<W: Write> Drop for TermionRenderer<W> {
+    fn drop(&mut self) {
+        write!(self.out, "\x1b[0m{}", ::termion::cursor::Show).ok();
+        self.out.flush().ok();
+    }
+}
+
+#[test]
+fn termion_diff_test() {
+    use colored::Color;
+
+    let mut r = TermionRenderer::new(vec![])
+        .unwrap()
+        .with_depth(ColorDepth::TrueColor);
+    let mut a = PaintBoard::new(2, 2, ' ');
+    a.paint_string(0, 0, "ab", None);
+    r.draw(&a).unwrap();
+    r.out.clear();
+    r.draw(&a).unwrap();
+    assert!(r.out.is_empty());
+    // two neighbours: one cursor move, one color
+    a.paint_string(3, 1, "xy", Some(Color::Red));
+    r.draw(&a).unwrap();
+    assert_eq!(r.out, b"\x1b[2;4H\x1b[0m\x1b[31mxy\x1b[0m");
+
+    // truecolor downgraded to the 256 color palette, and no colors at all
+    r.depth = ColorDepth::Ansi256;
+    a.paint_string(3, 1, "xy", Some(Color::TrueColor { r: 255, g: 0, b: 0 }));
+    r.out.clear();
+    r.draw(&a).unwrap();
+    assert_eq!(r.out, b"\x1b[2;4H\x1b[0m\x1b[38;5;196mxy\x1b[0m");
+    r.depth = ColorDepth::None;
+    a.paint_string(3, 1, "xy", Some(Color::Blue));
+    r.out.clear();
+    r.draw(&a).unwrap();
+    assert_eq!(r.out, b"\x1b[2;4H\x1b[0mxy\x1b[0m");
+}
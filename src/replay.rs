@@ -0,0 +1,360 @@
+//! Replays: the seed, the settings and every input of a game with the frame it happened on.
+//!
+//! The engine runs on fixed frames and the randomizer is seeded, so applying the same
+//! actions on the same frames plays the same game again.
+//!
+//! A replay file is plain text:
+//!
+//! ```text
+//! tetris-rs replay
+//! seed 42
+//! release_timeout 100
+//! actions
+//! 0 press left
+//! 9 release left
+//! 31 sink
+//! config
+//! [gameplay]
+//! ...
+//! ```
+
+use std::{fmt, fs, io, path::Path, str::FromStr, time::Duration};
+
+use crate::{
+    config,
+    env::{EnvConfig, Source},
+    game::{Tetris, FRAME},
+    handling::Input,
+};
+
+/// one input of the player
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    Press(Input),
+    Release(Input),
+    Rotate,
+    RotateCcw,
+    Rotate180,
+    Sink,
+    Hold,
+    /// pause or resume
+    TogglePause,
+    /// pause only, e.g. when the terminal lost focus
+    Pause,
+    Quit,
+}
+
+impl Action {
+    pub fn apply(self, t: &mut Tetris) {
+        match self {
+            Action::Press(input) => t.press(input),
+            Action::Release(input) => t.release(input),
+            Action::Rotate => t.event_rotate(),
+            Action::RotateCcw => t.event_rotate_ccw(),
+            Action::Rotate180 => t.event_rotate_180(),
+            Action::Sink => t.event_sink(),
+            Action::Hold => t.event_hold(),
+            Action::TogglePause => t.event_pause(),
+            Action::Pause => t.pause(),
+            Action::Quit => t.event_quit(),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Press(input) => write!(f, "press {}", input),
+            Action::Release(input) => write!(f, "release {}", input),
+            Action::Rotate => write!(f, "rotate"),
+            Action::RotateCcw => write!(f, "rotate_ccw"),
+            Action::Rotate180 => write!(f, "rotate_180"),
+            Action::Sink => write!(f, "sink"),
+            Action::Hold => write!(f, "hold"),
+            Action::TogglePause => write!(f, "toggle_pause"),
+            Action::Pause => write!(f, "pause"),
+            Action::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = |name: &str| {
+            name.parse::<Input>()
+                .map_err(|_| format!("unknown input {}", name))
+        };
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["press", name] => Ok(Action::Press(input(name)?)),
+            ["release", name] => Ok(Action::Release(input(name)?)),
+            ["rotate"] => Ok(Action::Rotate),
+            ["rotate_ccw"] => Ok(Action::RotateCcw),
+            ["rotate_180"] => Ok(Action::Rotate180),
+            ["sink"] => Ok(Action::Sink),
+            ["hold"] => Ok(Action::Hold),
+            ["toggle_pause"] => Ok(Action::TogglePause),
+            ["pause"] => Ok(Action::Pause),
+            ["quit"] => Ok(Action::Quit),
+            _ => Err(format!("unknown action {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// None when the game dealt from a custom randomizer, it can't be played back the same
+    pub seed: Option<u64>,
+    pub cfg: EnvConfig,
+    /// see `Handling::release_timeout`, it changes when held keys let go
+    pub release_timeout: Option<Duration>,
+    /// `(frame, action)` in the order they happened
+    pub actions: Vec<(u64, Action)>,
+}
+
+impl Replay {
+    /// start recording the game `t`, before it is started
+    pub fn new(t: &Tetris) -> Self {
+        Self {
+            seed: t.seed,
+            cfg: t.cfg.clone(),
+            release_timeout: t.handling.release_timeout,
+            actions: vec![],
+        }
+    }
+
+    /// apply `action` to `t` and keep it
+    pub fn record(&mut self, t: &mut Tetris, action: Action) {
+        self.actions.push((t.frames, action));
+        action.apply(t);
+    }
+
+    /// a new game with the recorded seed and settings, not started yet
+    pub fn game(&self) -> Tetris {
+        let mut t = Tetris::new(EnvConfig {
+            seed: self.seed,
+            ..self.cfg.clone()
+        });
+        t.handling.release_timeout = self.release_timeout;
+        t
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tetris-rs replay")?;
+        match self.seed {
+            Some(seed) => writeln!(f, "seed {}", seed)?,
+            None => writeln!(f, "seed none")?,
+        }
+        match self.release_timeout {
+            Some(d) => writeln!(f, "release_timeout {}", d.as_millis())?,
+            None => writeln!(f, "release_timeout none")?,
+        }
+        writeln!(f, "actions")?;
+        for (frame, action) in &self.actions {
+            writeln!(f, "{} {}", frame, action)?;
+        }
+        writeln!(f, "config")?;
+        write!(f, "{}", config::to_toml(&self.cfg))
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+        let mut next = |what: &str| lines.next().ok_or_else(|| format!("missing {}", what));
+        if next("header")?.1 != "tetris-rs replay" {
+            return Err("not a tetris-rs replay".to_string());
+        }
+        let seed = match next("seed")?.1.strip_prefix("seed ") {
+            Some("none") => None,
+            Some(v) => Some(
+                v.parse()
+                    .map_err(|_| "line 2: seed should be a number or none")?,
+            ),
+            None => return Err("line 2: missing seed".to_string()),
+        };
+        let release_timeout = match next("release_timeout")?.1.strip_prefix("release_timeout ") {
+            Some("none") => None,
+            Some(v) => {
+                Some(Duration::from_millis(v.parse().map_err(|_| {
+                    "line 3: release_timeout should be a number or none"
+                })?))
+            }
+            None => return Err("line 3: missing release_timeout".to_string()),
+        };
+        if next("actions")?.1 != "actions" {
+            return Err("line 4: missing actions".to_string());
+        }
+        let mut actions = vec![];
+        let mut config_line = None;
+        for (i, line) in lines.by_ref() {
+            if line == "config" {
+                config_line = Some(i + 1);
+                break;
+            }
+            let (frame, action) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("line {}: should be a frame and an action", i + 1))?;
+            let frame = frame
+                .parse()
+                .map_err(|_| format!("line {}: frame should be a number", i + 1))?;
+            let action = action
+                .parse()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            actions.push((frame, action));
+        }
+        let offset = config_line.ok_or("missing config")?;
+        let src: Vec<&str> = lines.map(|(_, line)| line).collect();
+        let mut cfg = config::parse(&src.join("\n"), EnvConfig::default()).map_err(|e| {
+            // 行号从配置开始算, 换成整个文件的行号
+            match e.line() {
+                Some(_) => format!("line {}", e.shift_line(offset)),
+                None => e.to_string(),
+            }
+        })?;
+        for source in cfg.sources.values_mut() {
+            if let Source::File { line, .. } = source {
+                *line += offset;
+            }
+        }
+        // 不合理的设置会让对局出错, 不回放
+        cfg.validate().map_err(|e| e.to_string())?;
+        Ok(Self {
+            seed,
+            cfg,
+            release_timeout,
+            actions,
+        })
+    }
+}
+
+/// plays a replay back frame by frame
+#[derive(Debug, Clone)]
+pub struct Player {
+    replay: Replay,
+    /// next action to apply
+    next: usize,
+    accumulator: Duration,
+}
+
+impl Player {
+    /// the player and the started game it drives
+    pub fn new(replay: Replay) -> (Self, Tetris) {
+        let mut t = replay.game();
+        t.start();
+        let player = Self {
+            replay,
+            next: 0,
+            accumulator: Duration::ZERO,
+        };
+        (player, t)
+    }
+
+    /// apply the actions of the current frame, then simulate it
+    pub fn step(&mut self, t: &mut Tetris) {
+        while let Some(&(frame, action)) = self.replay.actions.get(self.next) {
+            if frame > t.frames {
+                break;
+            }
+            action.apply(t);
+            self.next += 1;
+        }
+        t.tick(FRAME);
+    }
+
+    /// play back in real time, like `Tetris::tick`. returns the frames stepped.
+    pub fn tick(&mut self, t: &mut Tetris, dt: Duration) -> u32 {
+        self.accumulator += dt;
+        let mut frames = 0;
+        while self.accumulator >= FRAME && !self.is_done(t) {
+            self.accumulator -= FRAME;
+            self.step(t);
+            frames += 1;
+        }
+        frames
+    }
+
+    /// the game is over, or nothing will change any more: the game is paused
+    /// and no action is left for this frame. frames don't pass while paused,
+    /// so later actions would never come.
+    pub fn is_done(&self, t: &Tetris) -> bool {
+        t.is_over()
+            || (!t.is_running()
+                && self
+                    .replay
+                    .actions
+                    .get(self.next)
+                    .is_none_or(|&(frame, _)| frame > t.frames))
+    }
+
+    /// play to the end as fast as possible
+    pub fn run(&mut self, t: &mut Tetris) {
+        while !self.is_done(t) {
+            self.step(t);
+        }
+    }
+}
+
+#[test]
+fn replay_test() {
+    let mut t = Tetris::new(EnvConfig {
+        seed: Some(7),
+        ..Default::default()
+    });
+    let mut replay = Replay::new(&t);
+    t.start();
+    replay.record(&mut t, Action::Press(Input::Left));
+    t.tick(FRAME * 10);
+    replay.record(&mut t, Action::Release(Input::Left));
+    replay.record(&mut t, Action::Rotate);
+    replay.record(&mut t, Action::Sink);
+    t.tick(FRAME * 5);
+    replay.record(&mut t, Action::Hold);
+    replay.record(&mut t, Action::Sink);
+    replay.record(&mut t, Action::Quit);
+
+    assert_eq!(replay.seed, Some(7));
+    let text = replay.to_string();
+    assert!(text.contains("\n0 press left\n10 release left\n"));
+    let back: Replay = text.parse().unwrap();
+    assert_eq!(back.actions, replay.actions);
+
+    let (mut player, mut again) = Player::new(back);
+    player.run(&mut again);
+    assert_eq!(again.record.score, t.record.score);
+    assert_eq!(again.frames, t.frames);
+    assert_eq!(again.record.eliminate_rows, t.record.eliminate_rows);
+
+    // 暂停后帧数不再增加, 之后帧上的动作等不到, 不能一直空转
+    let paused = text.replace("10 release left", "10 pause\n20 release left");
+    let (mut player, mut again) = Player::new(paused.parse().unwrap());
+    player.run(&mut again);
+    assert!(again.is_paused());
+    assert_eq!(again.frames, 10);
+
+    let err = text.replace("10 release left", "10 jump").parse::<Replay>();
+    assert_eq!(err.unwrap_err(), "line 6: unknown action jump");
+
+    // an unseeded game keeps the seed it drew, a custom randomizer has none
+    assert!(Tetris::new(EnvConfig::default()).seed.is_some());
+    let custom = crate::randomizer::RandomizerKind::Bag7.build(false, Some(1));
+    assert_eq!(
+        Tetris::with_randomizer(EnvConfig::default(), custom).seed,
+        None
+    );
+}
@@ -0,0 +1,164 @@
+//! Super Rotation System: rotation states and wall/floor kick tables.
+//!
+//! Offsets are `(x, y)` with x to the right and y up, the same convention as
+//! the brick pixels. Every table starts with `(0, 0)` (no kick) and is tried in
+//! order, the first position that fits wins.
+
+use crate::bricks::Pixel;
+
+/// Orientation of a brick, `Spawn` is the orientation it enters the board with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Rotation {
+    /// 0
+    Spawn,
+    /// R, one clockwise turn from spawn
+    Right,
+    /// 2
+    Two,
+    /// L, one counter-clockwise turn from spawn
+    Left,
+}
+
+/// Direction of a rotation input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Turn {
+    Cw,
+    Ccw,
+    Half,
+}
+
+impl Rotation {
+    pub fn cw(self) -> Self {
+        match self {
+            Rotation::Spawn => Rotation::Right,
+            Rotation::Right => Rotation::Two,
+            Rotation::Two => Rotation::Left,
+            Rotation::Left => Rotation::Spawn,
+        }
+    }
+
+    pub fn ccw(self) -> Self {
+        self.cw().cw().cw()
+    }
+
+    pub fn flip(self) -> Self {
+        self.cw().cw()
+    }
+
+    pub fn turn(self, turn: Turn) -> Self {
+        match turn {
+            Turn::Cw => self.cw(),
+            Turn::Ccw => self.ccw(),
+            Turn::Half => self.flip(),
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Rotation::Spawn => 0,
+            Rotation::Right => 1,
+            Rotation::Two => 2,
+            Rotation::Left => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Rotation::Spawn => "0",
+            Rotation::Right => "R",
+            Rotation::Two => "2",
+            Rotation::Left => "L",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Which kick table a brick uses when rotating.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KickTable {
+    /// standard SRS table shared by J, L, S, T, Z (and the 3x3 feature bricks)
+    Jlstz,
+    /// the separate SRS table of the I brick
+    I,
+    /// small bricks (`Angle`, `Bean`): nudge one cell sideways or up
+    Small,
+    /// rotation never changes the occupied cells (`O`, `Dot`), no kicks needed
+    Fixed,
+}
+
+// [from][to] for the clockwise transitions 0->R, R->2, 2->L, L->0.
+// The counter-clockwise transitions are the same offsets negated.
+static JLSTZ_CW: [[Pixel; 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0->R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R->2
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2->L
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L->0
+];
+
+static JLSTZ_CCW: [[Pixel; 5]; 4] = [
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0->L
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R->0
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2->R
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L->2
+];
+
+static I_CW: [[Pixel; 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0->R
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R->2
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2->L
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L->0
+];
+
+static I_CCW: [[Pixel; 5]; 4] = [
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0->L
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R->0
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2->R
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L->2
+];
+
+// 180 degree turns are not part of the guideline, this is the widely used
+// SRS+ table: 0->2, R->L, 2->0, L->R. shared by all kicking bricks.
+static HALF: [[Pixel; 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)], // 0->2
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],   // R->L
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)], // 2->0
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)], // L->R
+];
+
+static SMALL: [Pixel; 4] = [(0, 0), (-1, 0), (1, 0), (0, 1)];
+
+static FIXED: [Pixel; 1] = [(0, 0)];
+
+/// Kick offsets to try, in order, when `table` rotates from `from` to `to`.
+pub fn kicks(table: KickTable, from: Rotation, to: Rotation) -> &'static [Pixel] {
+    let cw = from.cw() == to;
+    let half = from.flip() == to;
+    match table {
+        KickTable::Fixed => &FIXED,
+        KickTable::Jlstz | KickTable::I | KickTable::Small if half => &HALF[from.index()],
+        KickTable::Jlstz if cw => &JLSTZ_CW[from.index()],
+        KickTable::Jlstz => &JLSTZ_CCW[from.index()],
+        KickTable::I if cw => &I_CW[from.index()],
+        KickTable::I => &I_CCW[from.index()],
+        KickTable::Small => &SMALL,
+    }
+}
+
+#[test]
+fn srs_table_test() {
+    // R->0 must undo 0->R for every kick
+    for (a, b) in JLSTZ_CW[0].iter().zip(JLSTZ_CCW[1].iter()) {
+        assert_eq!((a.0, a.1), (-b.0, -b.1));
+    }
+    assert_eq!(
+        kicks(KickTable::I, Rotation::Left, Rotation::Spawn)[3],
+        (1, -2)
+    );
+    assert_eq!(
+        kicks(KickTable::Jlstz, Rotation::Two, Rotation::Spawn)[1],
+        (0, -1)
+    );
+    assert_eq!(Rotation::Left.turn(Turn::Half), Rotation::Right);
+}
@@ -0,0 +1,348 @@
+//! Results of finished games, kept in `$XDG_DATA_HOME/tetris-rs/scores`
+//! (`~/.local/share/tetris-rs/scores` when `XDG_DATA_HOME` is not set).
+//!
+//! One game per line, tab separated, new games are appended:
+//! `date mode width height feature_brick randomizer scoring start_level hold complete score rows level frames name`.
+//! Lines written before `start_level` and `hold` were kept count as level 1 with hold.
+//!
+//! Games only compete with games of the same `Category`, each has its own top table.
+
+use std::{
+    cmp::Ordering,
+    env, fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    env::EnvConfig, game::Tetris, mode::Mode, randomizer::RandomizerKind, scoring::ScoringKind,
+};
+
+/// places in a top table
+pub const TABLE_SIZE: usize = 10;
+
+/// the settings a score is compared under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Category {
+    pub mode: Mode,
+    pub width: usize,
+    pub height: usize,
+    pub feature_brick: bool,
+    pub randomizer: RandomizerKind,
+    pub scoring: ScoringKind,
+    pub start_level: usize,
+    pub hold: bool,
+}
+
+impl Category {
+    /// the category games played with `cfg` fall in
+    pub fn of(cfg: &EnvConfig) -> Self {
+        Self {
+            mode: cfg.mode,
+            width: cfg.width,
+            height: cfg.height,
+            feature_brick: cfg.feature_brick,
+            randomizer: cfg.randomizer,
+            scoring: cfg.scoring,
+            start_level: cfg.start_level,
+            hold: cfg.hold,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}x{}, level {}, {}, {} scoring{}{}",
+            self.mode,
+            self.width,
+            self.height,
+            self.start_level,
+            self.randomizer,
+            self.scoring,
+            if self.hold { "" } else { ", no hold" },
+            if self.feature_brick {
+                ", feature bricks"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub name: String,
+    /// seconds since the unix epoch
+    pub date: u64,
+    pub mode: Mode,
+    pub width: usize,
+    pub height: usize,
+    pub feature_brick: bool,
+    pub randomizer: RandomizerKind,
+    pub scoring: ScoringKind,
+    pub start_level: usize,
+    pub hold: bool,
+    /// the goal of the mode was reached, e.g. all 40 rows of a sprint
+    pub complete: bool,
+    pub score: i64,
+    pub rows: usize,
+    pub level: usize,
+    pub frames: u64,
+}
+
+impl Score {
+    /// the result of the game `t`
+    pub fn new(t: &Tetris, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            mode: t.cfg.mode,
+            width: t.cfg.width,
+            height: t.cfg.height,
+            feature_brick: t.cfg.feature_brick,
+            randomizer: t.cfg.randomizer,
+            scoring: t.cfg.scoring,
+            start_level: t.cfg.start_level,
+            hold: t.cfg.hold,
+            complete: t.cfg.mode.is_complete(&t.record, t.frames),
+            score: t.record.score,
+            rows: t.record.eliminate_rows,
+            level: t.record.level,
+            frames: t.frames,
+        }
+    }
+
+    pub fn category(&self) -> Category {
+        Category {
+            mode: self.mode,
+            width: self.width,
+            height: self.height,
+            feature_brick: self.feature_brick,
+            randomizer: self.randomizer,
+            scoring: self.scoring,
+            start_level: self.start_level,
+            hold: self.hold,
+        }
+    }
+
+    /// `Less` when `self` ranks above `other`: sprint by time, the other modes by score
+    pub fn rank(&self, other: &Score) -> Ordering {
+        match self.mode {
+            Mode::Sprint => (!self.complete, self.frames).cmp(&(!other.complete, other.frames)),
+            _ => other.score.cmp(&self.score),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields: Vec<&str> = line.split('\t').collect();
+        // 旧的行没有开始等级和 hold, 当作默认的 1 级和可以 hold
+        if fields.len() == 13 {
+            fields.splice(7..7, ["1", "true"]);
+        }
+        match fields[..] {
+            [date, mode, width, height, feature_brick, randomizer, scoring, start_level, hold, complete, score, rows, level, frames, name] => {
+                Some(Self {
+                    name: name.to_string(),
+                    date: date.parse().ok()?,
+                    mode: mode.parse().ok()?,
+                    width: width.parse().ok()?,
+                    height: height.parse().ok()?,
+                    feature_brick: feature_brick.parse().ok()?,
+                    randomizer: randomizer.parse().ok()?,
+                    scoring: scoring.parse().ok()?,
+                    start_level: start_level.parse().ok()?,
+                    hold: hold.parse().ok()?,
+                    complete: complete.parse().ok()?,
+                    score: score.parse().ok()?,
+                    rows: rows.parse().ok()?,
+                    level: level.parse().ok()?,
+                    frames: frames.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn line(&self) -> String {
+        // 名字里的制表符和换行会破坏格式
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.date,
+            self.mode,
+            self.width,
+            self.height,
+            self.feature_brick,
+            self.randomizer,
+            self.scoring,
+            self.start_level,
+            self.hold,
+            self.complete,
+            self.score,
+            self.rows,
+            self.level,
+            self.frames,
+            name
+        )
+    }
+}
+
+/// where the scores are kept
+pub fn path() -> Option<PathBuf> {
+    match env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")),
+    }
+    .map(|dir| dir.join("tetris-rs").join("scores"))
+}
+
+/// every saved game, oldest first. no file means no games, unreadable lines are skipped.
+pub fn load(path: &Path) -> io::Result<Vec<Score>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().filter_map(Score::parse).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// add a game to the file, creating it if needed
+pub fn append(path: &Path, score: &Score) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", score.line())
+}
+
+/// the best `n` games of `mode`, best first
+pub fn best(scores: &[Score], mode: Mode, n: usize) -> Vec<&Score> {
+    let mut games: Vec<&Score> = scores.iter().filter(|s| s.mode == mode).collect();
+    games.sort_by(|a, b| a.rank(b));
+    games.truncate(n);
+    games
+}
+
+/// the top `n` games of `category`, best first
+pub fn table(scores: &[Score], category: Category, n: usize) -> Vec<&Score> {
+    let mut games: Vec<&Score> = scores.iter().filter(|s| s.category() == category).collect();
+    // 排序是稳定的, 同分时先来的在前
+    games.sort_by(|a, b| a.rank(b));
+    games.truncate(n);
+    games
+}
+
+/// every category with saved games, most played first
+pub fn categories(scores: &[Score]) -> Vec<Category> {
+    let mut counts: Vec<(Category, usize)> = vec![];
+    for s in scores {
+        match counts.iter_mut().find(|(c, _)| *c == s.category()) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((s.category(), 1)),
+        }
+    }
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    counts.into_iter().map(|(c, _)| c).collect()
+}
+
+/// the place (from 1) `score` takes in the top `n` of its category, None when it doesn't make it.
+/// games that didn't score or didn't finish a sprint are no records.
+pub fn place(scores: &[Score], score: &Score, n: usize) -> Option<usize> {
+    let worthy = match score.mode {
+        Mode::Sprint => score.complete,
+        _ => score.score > 0,
+    };
+    if !worthy {
+        return None;
+    }
+    let better = table(scores, score.category(), n)
+        .iter()
+        .filter(|s| s.rank(score) != Ordering::Greater)
+        .count();
+    (better < n).then_some(better + 1)
+}
+
+/// the name to save scores under when none was entered
+pub fn default_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "player".to_string())
+}
+
+#[test]
+fn scores_test() {
+    let mut t = Tetris::new(EnvConfig {
+        mode: Mode::Sprint,
+        ..Default::default()
+    });
+    t.start();
+    t.event_sink();
+    let mut a = Score::new(&t, "ann\tb");
+    let line = a.line();
+    assert!(line.ends_with("\tann b"));
+    let back = Score::parse(&line).unwrap();
+    assert_eq!(back.score, a.score);
+    assert_eq!(back.mode, Mode::Sprint);
+    assert!(Score::parse("garbage").is_none());
+    // lines from before start_level and hold were saved
+    let old = Score::parse("1\tmarathon\t10\t20\ttrue\tbag7\tguideline\tfalse\t500\t4\t1\t900\tbo")
+        .unwrap();
+    assert_eq!((old.start_level, old.hold, old.score), (1, true, 500));
+    assert_eq!(old.name, "bo");
+
+    // a finished sprint ranks above an unfinished faster one
+    a.complete = true;
+    a.frames = 5000;
+    let b = Score {
+        frames: 100,
+        ..back
+    };
+    let scores = [b, a];
+    assert_eq!(best(&scores, Mode::Sprint, 5)[0].frames, 5000);
+    assert!(best(&scores, Mode::Ultra, 5).is_empty());
+}
+
+#[test]
+fn table_test() {
+    let t = Tetris::new(EnvConfig::default());
+    let game = |score| Score {
+        score,
+        ..Score::new(&t, "p")
+    };
+    let mut saved: Vec<Score> = (1..=3).map(|i| game(i * 100)).collect();
+    // another board size is another table
+    saved.push(Score {
+        width: 12,
+        ..game(1000)
+    });
+
+    // so are another randomizer and start level
+    saved.push(Score {
+        randomizer: RandomizerKind::Nes,
+        ..game(2000)
+    });
+    saved.push(Score {
+        start_level: 15,
+        ..game(3000)
+    });
+
+    let category = saved[0].category();
+    let top = table(&saved, category, 2);
+    assert_eq!(top.iter().map(|s| s.score).collect::<Vec<_>>(), [300, 200]);
+    assert_eq!(categories(&saved)[0], category);
+    assert_eq!(categories(&saved).len(), 4);
+
+    assert_eq!(place(&saved, &game(250), 2), Some(2));
+    assert_eq!(place(&saved, &game(150), 2), None);
+    assert_eq!(place(&saved, &game(150), 10), Some(3));
+    assert_eq!(place(&saved, &game(0), 10), None);
+}
@@ -0,0 +1,180 @@
+//! Scoring rulesets.
+//!
+//! A ruleset turns locks and drops into points and keeps the combo and
+//! back-to-back state of the `Record`. Pick a built-in one with `ScoringKind`
+//! (env `SCORING`) or replace `Tetris::scoring` with your own.
+
+use crate::{
+    record::Record,
+    spin::{LineClear, TSpin},
+};
+
+pub trait Scoring: std::fmt::Debug + Send {
+    /// score one lock, cleared rows or not
+    fn score_clear(&self, record: &mut Record, clear: LineClear);
+
+    /// points for soft dropping `rows` rows
+    fn soft_drop(&self, rows: usize) -> i64;
+
+    /// points for hard dropping `rows` rows
+    fn hard_drop(&self, rows: usize) -> i64;
+
+    fn clone_box(&self) -> Box<dyn Scoring>;
+}
+
+impl Clone for Box<dyn Scoring> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum ScoringKind {
+    /// the original tetris-rs formula
+    #[default]
+    Classic,
+    Guideline,
+}
+
+impl ScoringKind {
+    pub const ALL: [ScoringKind; 2] = [ScoringKind::Classic, ScoringKind::Guideline];
+
+    pub fn build(self) -> Box<dyn Scoring> {
+        match self {
+            ScoringKind::Classic => Box::new(Classic),
+            ScoringKind::Guideline => Box::new(Guideline),
+        }
+    }
+}
+
+/// The original tetris-rs formula: every row is worth `200 + combo * 60` where the
+/// combo counts rows cleared in a row, T-spins add the guideline bonus, 1 point per dropped row.
+#[derive(Debug, Clone, Copy)]
+pub struct Classic;
+
+impl Scoring for Classic {
+    fn score_clear(&self, record: &mut Record, clear: LineClear) {
+        record.count(clear);
+        record.score += match clear.tspin {
+            TSpin::None => 0,
+            TSpin::Mini => [100, 200, 400][clear.lines.min(2)],
+            TSpin::Full => [400, 800, 1200, 1600][clear.lines.min(3)],
+        };
+        if clear.lines == 0 {
+            record.combo = 0;
+            return;
+        }
+        for _ in 0..clear.lines {
+            record.score += 200 + (record.combo * 60) as i64;
+            record.combo_up();
+        }
+    }
+
+    fn soft_drop(&self, rows: usize) -> i64 {
+        rows as i64
+    }
+
+    fn hard_drop(&self, rows: usize) -> i64 {
+        rows as i64
+    }
+
+    fn clone_box(&self) -> Box<dyn Scoring> {
+        Box::new(*self)
+    }
+}
+
+/// Guideline scoring: clear-type values times level, x1.5 for back-to-back
+/// difficult clears, `50 * combo * level` for consecutive clears, perfect clear
+/// bonuses, 1 point per soft dropped row and 2 per hard dropped row.
+#[derive(Debug, Clone, Copy)]
+pub struct Guideline;
+
+impl Guideline {
+    fn base(clear: LineClear) -> i64 {
+        match clear.tspin {
+            TSpin::None => [0, 100, 300, 500, 800][clear.lines.min(4)],
+            TSpin::Mini => [100, 200, 400][clear.lines.min(2)],
+            TSpin::Full => [400, 800, 1200, 1600][clear.lines.min(3)],
+        }
+    }
+
+    fn perfect_clear(lines: usize, back_to_back_tetris: bool) -> i64 {
+        match lines {
+            0 => 0,
+            1 => 800,
+            2 => 1200,
+            3 => 1800,
+            _ if back_to_back_tetris => 3200,
+            _ => 2000,
+        }
+    }
+}
+
+impl Scoring for Guideline {
+    fn score_clear(&self, record: &mut Record, clear: LineClear) {
+        record.count(clear);
+        let level = record.level as i64;
+        let mut points = Self::base(clear) * level;
+        if clear.lines == 0 {
+            // T-spin without rows: no combo, back-to-back chain untouched
+            record.combo = 0;
+            record.score += points;
+            return;
+        }
+        let difficult = clear.is_difficult();
+        let back_to_back = difficult && record.back_to_back;
+        if back_to_back {
+            points = points * 3 / 2;
+        }
+        points += 50 * record.combo as i64 * level;
+        if clear.perfect {
+            points += Self::perfect_clear(clear.lines, back_to_back && clear.lines >= 4) * level;
+        }
+        record.score += points;
+        record.back_to_back = difficult;
+        record.combo_up();
+    }
+
+    fn soft_drop(&self, rows: usize) -> i64 {
+        rows as i64
+    }
+
+    fn hard_drop(&self, rows: usize) -> i64 {
+        2 * rows as i64
+    }
+
+    fn clone_box(&self) -> Box<dyn Scoring> {
+        Box::new(*self)
+    }
+}
+
+#[test]
+fn guideline_test() {
+    let clear = |lines, tspin| LineClear {
+        lines,
+        tspin,
+        perfect: false,
+    };
+    let mut r = Record::new();
+    r.level = 2;
+    let g = Guideline;
+    g.score_clear(&mut r, clear(4, TSpin::None));
+    assert_eq!(r.score, 1600);
+    // back-to-back tetris with 1 combo
+    g.score_clear(&mut r, clear(4, TSpin::None));
+    assert_eq!(r.score, 1600 + 2400 + 100);
+    // a single breaks the chain
+    g.score_clear(&mut r, clear(1, TSpin::None));
+    assert!(!r.back_to_back);
+    g.score_clear(&mut r, clear(0, TSpin::None));
+    assert_eq!(r.combo, 0);
+    assert_eq!(r.eliminate_rows, 9);
+
+    // classic: a tetris is worth four combo'd rows
+    let mut r = Record::new();
+    Classic.score_clear(&mut r, clear(4, TSpin::None));
+    assert_eq!(r.score, 200 + 260 + 320 + 380);
+}
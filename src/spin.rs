@@ -0,0 +1,122 @@
+//! T-spin detection (3-corner rule) and the clear type of a lock.
+
+use crate::{
+    bricks::Pixel,
+    rotation::{Rotation, Turn},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+/// the four diagonal neighbours of the T centre (x right, y up)
+pub static CORNERS: [Pixel; 4] = [(-1, 1), (1, 1), (1, -1), (-1, -1)];
+
+/// the two corners on the side the T points to
+pub fn front_corners(rotation: Rotation) -> [Pixel; 2] {
+    match rotation {
+        Rotation::Spawn => [(-1, 1), (1, 1)],
+        Rotation::Right => [(1, 1), (1, -1)],
+        Rotation::Two => [(1, -1), (-1, -1)],
+        Rotation::Left => [(-1, -1), (-1, 1)],
+    }
+}
+
+/// 3-corner rule. `occupied` tells whether a corner (relative to the T centre) is a wall,
+/// the floor or a filled cell, `kick` is the index of the kick used by the last rotation.
+/// a mini is upgraded to a full T-spin when the last kick was the long `(±1, ±2)` one of
+/// a 90 degree turn (TST and fin kicks). 180 degree kicks never upgrade.
+pub fn classify(
+    rotation: Rotation,
+    turn: Turn,
+    kick: usize,
+    occupied: impl Fn(Pixel) -> bool,
+) -> TSpin {
+    let corners = CORNERS.iter().filter(|&&c| occupied(c)).count();
+    if corners < 3 {
+        return TSpin::None;
+    }
+    let front = front_corners(rotation)
+        .iter()
+        .filter(|&&c| occupied(c))
+        .count();
+    if front == 2 || (turn != Turn::Half && kick == 4) {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+/// what a single lock did: rows cleared and whether it was a T-spin
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LineClear {
+    pub lines: usize,
+    pub tspin: TSpin,
+    /// the board is empty afterwards
+    pub perfect: bool,
+}
+
+impl LineClear {
+    /// worth announcing: any cleared row or any T-spin
+    pub fn is_notable(&self) -> bool {
+        self.lines > 0 || self.tspin != TSpin::None
+    }
+
+    /// tetris or any T-spin that clears rows, these keep a back-to-back chain going
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.lines > 0 && self.tspin != TSpin::None)
+    }
+}
+
+impl std::fmt::Display for LineClear {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = match self.lines {
+            0 => "",
+            1 => "single",
+            2 => "double",
+            3 => "triple",
+            4 => "tetris",
+            _ => "multi",
+        };
+        if self.perfect {
+            write!(f, "perfect clear ")?;
+        }
+        match (self.tspin, self.lines) {
+            (TSpin::None, _) => write!(f, "{}", lines),
+            (TSpin::Mini, 0) => write!(f, "mini t-spin"),
+            (TSpin::Mini, _) => write!(f, "mini t-spin {}", lines),
+            (TSpin::Full, 0) => write!(f, "t-spin"),
+            (TSpin::Full, _) => write!(f, "t-spin {}", lines),
+        }
+    }
+}
+
+#[test]
+fn classify_test() {
+    // T pointing up with both front corners and one back corner filled
+    let filled = [(-1, 1), (1, 1), (-1, -1)];
+    let t = classify(Rotation::Spawn, Turn::Cw, 0, |c| filled.contains(&c));
+    assert_eq!(t, TSpin::Full);
+    // pointing down into the same cells: only one front corner -> mini
+    let t = classify(Rotation::Two, Turn::Cw, 0, |c| filled.contains(&c));
+    assert_eq!(t, TSpin::Mini);
+    // unless it got there with the last kick
+    let t = classify(Rotation::Two, Turn::Ccw, 4, |c| filled.contains(&c));
+    assert_eq!(t, TSpin::Full);
+    // index 4 of the 180 degree table is an ordinary kick
+    let t = classify(Rotation::Two, Turn::Half, 4, |c| filled.contains(&c));
+    assert_eq!(t, TSpin::Mini);
+    // two corners are not enough
+    let t = classify(Rotation::Spawn, Turn::Cw, 0, |c| c.1 > 0);
+    assert_eq!(t, TSpin::None);
+
+    let clear = LineClear {
+        lines: 2,
+        tspin: TSpin::Full,
+        perfect: false,
+    };
+    assert_eq!(clear.to_string(), "t-spin double");
+}
@@ -0,0 +1,96 @@
+//! Board textures: the text drawn for each kind of board cell.
+//!
+//! A cell can be several terminal columns wide, e.g. `██` or `[]`, which makes the
+//! board look square since terminal cells are about twice as high as wide.
+//! The compact preset instead packs two board rows into one terminal row with half blocks.
+
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum TexturePreset {
+    /// one column per cell: `#`, walls `H`
+    #[default]
+    Ascii,
+    /// two columns per cell: `[]`
+    Brackets,
+    /// two columns per cell: `██`
+    Blocks,
+    /// half blocks, two board rows per terminal row
+    Compact,
+}
+
+impl TexturePreset {
+    pub const ALL: [TexturePreset; 4] = [
+        TexturePreset::Ascii,
+        TexturePreset::Brackets,
+        TexturePreset::Blocks,
+        TexturePreset::Compact,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Texture {
+    pub full: String,
+    pub wall: String,
+    pub empty: String,
+    pub shadow: String,
+    /// draw the board with half blocks, two rows per terminal row.
+    /// the strings are then only used for the hold box and the next queue.
+    pub compact: bool,
+}
+
+impl Default for Texture {
+    fn default() -> Self {
+        Self::preset(TexturePreset::default())
+    }
+}
+
+impl Texture {
+    pub fn preset(preset: TexturePreset) -> Self {
+        let (full, wall, empty, shadow) = match preset {
+            TexturePreset::Ascii => ("#", "H", " ", "."),
+            TexturePreset::Brackets => ("[]", "##", "  ", " ."),
+            TexturePreset::Blocks => ("██", "▓▓", "  ", "░░"),
+            TexturePreset::Compact => ("█", "█", " ", "░"),
+        };
+        Self {
+            full: full.to_string(),
+            wall: wall.to_string(),
+            empty: empty.to_string(),
+            shadow: shadow.to_string(),
+            compact: preset == TexturePreset::Compact,
+        }
+    }
+
+    /// terminal columns per board cell: the longest of the strings
+    pub fn width(&self) -> usize {
+        [&self.full, &self.wall, &self.empty, &self.shadow]
+            .iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// `s` repeated or cut to exactly one cell, so shorter strings still line up
+    pub fn cell(&self, s: &str) -> String {
+        if s.is_empty() {
+            return " ".repeat(self.width());
+        }
+        s.chars().cycle().take(self.width()).collect()
+    }
+}
+
+#[test]
+fn texture_test() {
+    let t = Texture::preset(TexturePreset::Blocks);
+    assert_eq!(t.width(), 2);
+    let t = Texture {
+        full: "[]".to_string(),
+        ..Texture::default()
+    };
+    assert_eq!(t.cell(&t.wall), "HH");
+    assert_eq!(t.cell(&t.full), "[]");
+    assert_eq!("compact".parse(), Ok(TexturePreset::Compact));
+}
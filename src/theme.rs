@@ -0,0 +1,379 @@
+//! Color themes and color depth.
+//!
+//! A `Theme` picks the color of every brick, the walls, the ghost and the text.
+//! `ColorDepth` is what the terminal can show: truecolor themes are downgraded to
+//! the 256 or 16 color palette when needed, and `NO_COLOR` turns colors off.
+
+use std::env;
+
+use colored::Color;
+
+use crate::bricks::{Brick, BrickType};
+
+#[derive(
+    strum_macros::EnumString, strum_macros::Display, Debug, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum ThemePreset {
+    /// the original tetris-rs colors
+    #[default]
+    Classic,
+    /// guideline colors: cyan I, yellow O, purple T, green S, red Z, blue J, orange L
+    Guideline,
+    /// Okabe-Ito palette, distinguishable with the common kinds of color blindness
+    Colorblind,
+    /// high contrast bright colors for the 16 color palette
+    Contrast,
+    /// no colors
+    Mono,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 5] = [
+        ThemePreset::Classic,
+        ThemePreset::Guideline,
+        ThemePreset::Colorblind,
+        ThemePreset::Contrast,
+        ThemePreset::Mono,
+    ];
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::TrueColor { r, g, b }
+}
+
+/// orange, sky blue, bluish green, yellow, blue, vermillion, reddish purple
+const OKABE_ITO: [Color; 7] = [
+    rgb(0xe6, 0x9f, 0x00),
+    rgb(0x56, 0xb4, 0xe9),
+    rgb(0x00, 0x9e, 0x73),
+    rgb(0xf0, 0xe4, 0x42),
+    rgb(0x00, 0x72, 0xb2),
+    rgb(0xd5, 0x5e, 0x00),
+    rgb(0xcc, 0x79, 0xa7),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// brick colors in `BrickType` order
+    pub pieces: [Color; 12],
+    pub wall: Option<Color>,
+    pub ghost: Option<Color>,
+    /// labels of the side panel
+    pub text: Option<Color>,
+    /// help line and highlights
+    pub accent: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::default())
+    }
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Self {
+        let classic = BRICK_TYPES.map(|t| Brick::new(t).color);
+        match preset {
+            ThemePreset::Classic => Self {
+                pieces: classic,
+                wall: None,
+                ghost: None,
+                text: None,
+                accent: Some(Color::BrightRed),
+            },
+            ThemePreset::Guideline => {
+                let mut pieces = classic;
+                pieces[..7].copy_from_slice(&[
+                    Color::Cyan,
+                    Color::Yellow,
+                    rgb(0xa0, 0x00, 0xf0),
+                    Color::Green,
+                    Color::Red,
+                    rgb(0xff, 0xa5, 0x00),
+                    Color::Blue,
+                ]);
+                Self {
+                    pieces,
+                    wall: Some(Color::BrightBlack),
+                    ghost: Some(Color::BrightBlack),
+                    text: None,
+                    accent: Some(Color::BrightRed),
+                }
+            }
+            ThemePreset::Colorblind => Self {
+                // 颜色不够分时, 特殊方块再轮一遍
+                pieces: std::array::from_fn(|i| OKABE_ITO[i % OKABE_ITO.len()]),
+                wall: Some(Color::White),
+                ghost: Some(Color::BrightBlack),
+                text: None,
+                accent: Some(OKABE_ITO[0]),
+            },
+            ThemePreset::Contrast => Self {
+                pieces: [
+                    Color::BrightCyan,
+                    Color::BrightYellow,
+                    Color::BrightMagenta,
+                    Color::BrightGreen,
+                    Color::BrightRed,
+                    Color::BrightWhite,
+                    Color::BrightBlue,
+                    Color::BrightMagenta,
+                    Color::BrightBlue,
+                    Color::BrightGreen,
+                    Color::BrightYellow,
+                    Color::BrightCyan,
+                ],
+                wall: Some(Color::BrightWhite),
+                ghost: Some(Color::White),
+                text: Some(Color::BrightWhite),
+                accent: Some(Color::BrightYellow),
+            },
+            ThemePreset::Mono => Self {
+                pieces: [Color::White; 12],
+                wall: None,
+                ghost: None,
+                text: None,
+                accent: None,
+            },
+        }
+    }
+
+    pub fn piece(&self, brick_type: BrickType) -> Color {
+        self.pieces[brick_type as usize]
+    }
+
+    /// a new brick in the theme's color
+    pub fn brick(&self, brick_type: BrickType) -> Brick {
+        let mut brick = Brick::new(brick_type);
+        brick.color = self.piece(brick_type);
+        brick
+    }
+}
+
+/// the name `parse_color` reads back
+pub fn color_name(color: Color) -> String {
+    match color {
+        Color::TrueColor { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        // Debug 是 BrightBlue 这样的驼峰
+        c => {
+            let name = format!("{:?}", c);
+            match name.strip_prefix("Bright") {
+                Some(rest) => format!("bright_{}", rest.to_lowercase()),
+                None => name.to_lowercase(),
+            }
+        }
+    }
+}
+
+/// a color name as `colored` knows it (`red`, `bright blue`, ...) or `#rrggbb`
+pub fn parse_color(s: &str) -> Option<Color> {
+    let s = s.trim();
+    match s.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let v = u32::from_str_radix(hex, 16).ok()?;
+            Some(rgb((v >> 16) as u8, (v >> 8) as u8, v as u8))
+        }
+        Some(_) => None,
+        None => s.replace(['_', '-'], " ").parse().ok(),
+    }
+}
+
+/// every brick type, in `BrickType` order
+const BRICK_TYPES: [BrickType; 12] = [
+    BrickType::I,
+    BrickType::O,
+    BrickType::T,
+    BrickType::S,
+    BrickType::Z,
+    BrickType::L,
+    BrickType::J,
+    BrickType::Dot,
+    BrickType::Desk,
+    BrickType::Angle,
+    BrickType::W,
+    BrickType::Bean,
+];
+
+/// colors the terminal can show
+#[derive(
+    strum_macros::EnumString,
+    strum_macros::Display,
+    Debug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    PartialOrd,
+    Ord,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum ColorDepth {
+    #[strum(serialize = "none", serialize = "off")]
+    None,
+    #[strum(serialize = "16")]
+    Ansi16,
+    #[strum(serialize = "256")]
+    Ansi256,
+    #[strum(serialize = "truecolor", serialize = "24bit")]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default();
+        Self::from_env(&var("NO_COLOR"), &var("COLORTERM"), &var("TERM"))
+    }
+
+    fn from_env(no_color: &str, colorterm: &str, term: &str) -> Self {
+        if !no_color.is_empty() || term == "dumb" {
+            ColorDepth::None
+        } else if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// `color` as the terminal will show it, None when colors are off.
+    /// 256 color indices come back as `Indexed`, the 16 named colors and truecolor as they are.
+    pub fn convert(self, color: Color) -> Option<DepthColor> {
+        match (self, color) {
+            (ColorDepth::None, _) => None,
+            (ColorDepth::TrueColor, c) => Some(DepthColor::Named(c)),
+            (ColorDepth::Ansi256, Color::TrueColor { r, g, b }) => {
+                Some(DepthColor::Indexed(ansi256(r, g, b)))
+            }
+            (ColorDepth::Ansi16, Color::TrueColor { r, g, b }) => {
+                Some(DepthColor::Named(ansi16(r, g, b)))
+            }
+            (_, c) => Some(DepthColor::Named(c)),
+        }
+    }
+}
+
+/// a color after `ColorDepth::convert`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DepthColor {
+    /// one of the 16 named colors, or truecolor
+    Named(Color),
+    /// index into the 256 color palette
+    Indexed(u8),
+}
+
+impl DepthColor {
+    /// SGR parameters setting this color, foreground or background
+    pub fn sgr(self, background: bool) -> String {
+        match (self, background) {
+            (DepthColor::Named(c), false) => c.to_fg_str().to_string(),
+            (DepthColor::Named(c), true) => c.to_bg_str().to_string(),
+            (DepthColor::Indexed(i), false) => format!("38;5;{}", i),
+            (DepthColor::Indexed(i), true) => format!("48;5;{}", i),
+        }
+    }
+}
+
+/// nearest color of the 6x6x6 cube or the grey ramp of the 256 color palette
+pub fn ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| -> u8 {
+        match v {
+            0..=47 => 0,
+            48..=114 => 1,
+            _ => (v - 35) / 40,
+        }
+    };
+    let value = |l: u8| if l == 0 { 0 } else { 55 + 40 * l as i32 };
+    let (lr, lg, lb) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * lr + 6 * lg + lb;
+    let cube_dist = dist((r, g, b), (value(lr), value(lg), value(lb)));
+    // 灰阶 232..=255: 8, 18, ..., 238
+    let avg = (r as i32 + g as i32 + b as i32) / 3;
+    let grey = ((avg - 8).max(0) / 10).min(23);
+    let grey_value = 8 + 10 * grey;
+    match dist((r, g, b), (grey_value, grey_value, grey_value)) < cube_dist {
+        true => 232 + grey as u8,
+        false => cube,
+    }
+}
+
+fn dist((r, g, b): (u8, u8, u8), (r2, g2, b2): (i32, i32, i32)) -> i32 {
+    let (dr, dg, db) = (r as i32 - r2, g as i32 - g2, b as i32 - b2);
+    dr * dr + dg * dg + db * db
+}
+
+/// nearest of the 16 named colors, by the usual xterm values.
+/// saturated colors only map to colored entries, so a pink brick does not turn grey.
+pub fn ansi16(r: u8, g: u8, b: u8) -> Color {
+    const PALETTE: [(Color, (i32, i32, i32)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::White, (229, 229, 229)),
+        (Color::BrightBlack, (127, 127, 127)),
+        (Color::BrightRed, (255, 0, 0)),
+        (Color::BrightGreen, (0, 255, 0)),
+        (Color::BrightYellow, (255, 255, 0)),
+        (Color::BrightBlue, (92, 92, 255)),
+        (Color::BrightMagenta, (255, 0, 255)),
+        (Color::BrightCyan, (0, 255, 255)),
+        (Color::BrightWhite, (255, 255, 255)),
+    ];
+    let saturated = r.max(g).max(b) - r.min(g).min(b) >= 40;
+    let grey = |c: &Color| {
+        matches!(
+            c,
+            Color::Black | Color::White | Color::BrightBlack | Color::BrightWhite
+        )
+    };
+    PALETTE
+        .iter()
+        .filter(|(c, _)| grey(c) != saturated)
+        .min_by_key(|(_, v)| dist((r, g, b), *v))
+        .map(|&(c, _)| c)
+        .unwrap_or(Color::White)
+}
+
+#[test]
+fn color_depth_test() {
+    assert_eq!(ColorDepth::from_env("1", "truecolor", ""), ColorDepth::None);
+    assert_eq!(
+        ColorDepth::from_env("", "truecolor", "xterm"),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::from_env("", "", "xterm-256color"),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(ColorDepth::from_env("", "", "linux"), ColorDepth::Ansi16);
+
+    assert_eq!(ansi256(0, 0, 0), 16);
+    assert_eq!(ansi256(255, 0, 0), 196);
+    assert_eq!(ansi256(128, 128, 128), 244);
+    assert_eq!(ansi16(0xef, 0x6b, 0x81), Color::Magenta);
+    assert_eq!(ansi16(120, 120, 130), Color::BrightBlack);
+    let c = rgb(0x64, 0x95, 0xed);
+    assert_eq!(ColorDepth::None.convert(c), None);
+    assert_eq!(
+        ColorDepth::Ansi256.convert(c).unwrap().sgr(false),
+        "38;5;69"
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.convert(Color::Red),
+        Some(DepthColor::Named(Color::Red))
+    );
+
+    assert_eq!(Theme::default().piece(BrickType::I), Color::Cyan);
+    assert_eq!(parse_color("#6495ed"), Some(c));
+    assert_eq!(parse_color("bright_blue"), Some(Color::BrightBlue));
+    assert_eq!(parse_color("nope"), None);
+    assert_eq!(color_name(Color::BrightBlue), "bright_blue");
+    assert_eq!(parse_color(&color_name(c)), Some(c));
+    assert_eq!("colorblind".parse(), Ok(ThemePreset::Colorblind));
+}